@@ -68,8 +68,7 @@ impl Token {
 
     pub fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue> {
         let key = DataKey::Allowance(AllowanceDataKey { from, spender });
-        let allowance = e.storage().temporary().get::<_, AllowanceValue>(&key);
-        allowance
+        e.storage().temporary().get::<_, AllowanceValue>(&key)
     }
 }
 
@@ -1,3 +1,6 @@
+use crate::lock::read_locked_balance;
+use crate::points::checkpoint_points;
+use crate::rewards::checkpoint_rewards;
 use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
 use soroban_sdk::{Address, Env};
 
@@ -22,14 +25,40 @@ fn write_balance(e: &Env, addr: Address, amount: i128) {
 }
 
 pub fn receive_balance(e: &Env, addr: Address, amount: i128) {
+    checkpoint_rewards(e, addr.clone());
+    checkpoint_points(e, addr.clone());
     let balance = read_balance(e, addr.clone());
     write_balance(e, addr, balance + amount);
 }
 
 pub fn spend_balance(e: &Env, addr: Address, amount: i128) {
+    checkpoint_rewards(e, addr.clone());
+    checkpoint_points(e, addr.clone());
     let balance = read_balance(e, addr.clone());
     if balance < amount {
         panic!("insufficient balance");
     }
+    if balance - read_locked_balance(e, addr.clone()) < amount {
+        panic!("balance is locked");
+    }
     write_balance(e, addr, balance - amount);
 }
+
+// Per-holder amounts other than the balance, kept alive as long as it is
+pub fn read_holder_amount(e: &Env, key: &DataKey) -> i128 {
+    if let Some(value) = e.storage().persistent().get::<DataKey, i128>(key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        value
+    } else {
+        0
+    }
+}
+
+pub fn write_holder_amount(e: &Env, key: &DataKey, value: i128) {
+    e.storage().persistent().set(key, &value);
+    e.storage()
+        .persistent()
+        .extend_ttl(key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
@@ -6,12 +6,23 @@ use crate::allowance::{read_allowance, spend_allowance, write_allowance};
 use crate::balance::{read_balance, receive_balance, spend_balance};
 use crate::error::PumpTokenError;
 use crate::event;
+use crate::lock::{
+    lock_balance, read_boost, read_locked_balance, read_total_weight, unlock_balance,
+};
 use crate::metadata::{read_decimal, read_name, read_symbol, write_metadata, DECIMAL};
 use crate::minter::{read_minter, write_minter};
+use crate::points::{
+    read_multiplier_periods, read_points, read_total_points, update_points_index,
+    write_multiplier_periods, MultiplierPeriod, MAX_MULTIPLIER_PERIODS,
+};
+use crate::rewards::{
+    distribute_rewards, is_total_supply_seeded, read_accrued_rewards, read_reward_per_token,
+    read_total_supply, take_accrued_rewards, write_total_supply, write_total_supply_seeded,
+};
 use crate::storage_types::{AllowanceDataKey, AllowanceValue, DataKey};
 use crate::storage_types::{INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
 use soroban_sdk::token::{self, Interface as _};
-use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, String};
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, String, Vec};
 use soroban_token_sdk::metadata::TokenMetadata;
 use soroban_token_sdk::TokenUtils;
 
@@ -35,6 +46,28 @@ pub trait PumpTokenTrait {
     fn get_minter(e: Env) -> Address;
 
     fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue>;
+
+    fn notify_reward(e: Env, amount: i128) -> Result<i128, PumpTokenError>;
+    fn claim_rewards(e: Env, user: Address) -> i128;
+    fn accrued_rewards(e: Env, user: Address) -> i128;
+    fn reward_per_token(e: Env) -> i128;
+    fn total_supply(e: Env) -> i128;
+
+    fn set_points_multiplier(
+        e: Env,
+        start: u64,
+        end: u64,
+        multiplier: i128,
+    ) -> Result<(), PumpTokenError>;
+    fn remove_points_multiplier(e: Env, start: u64) -> Result<(), PumpTokenError>;
+    fn get_points_multipliers(e: Env) -> Vec<MultiplierPeriod>;
+    fn get_points(e: Env, user: Address) -> i128;
+    fn get_total_points(e: Env) -> i128;
+
+    fn lock(e: Env, user: Address, amount: i128, boost: i128);
+    fn unlock(e: Env, user: Address, amount: i128, boost: i128);
+    fn locked_balance(e: Env, user: Address) -> i128;
+    fn get_boost(e: Env, user: Address) -> i128;
 }
 
 #[contract]
@@ -49,6 +82,8 @@ impl PumpTokenTrait for PumpToken {
         write_administrator(&e, &admin);
 
         write_minter(&e, &minter);
+        // Nothing is minted yet, so the tracked supply starts out exact
+        write_total_supply_seeded(&e);
 
         write_metadata(
             &e,
@@ -126,6 +161,7 @@ impl PumpTokenTrait for PumpToken {
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
         receive_balance(&e, to.clone(), amount);
+        write_total_supply(&e, read_total_supply(&e) + amount);
         TokenUtils::new(&e).events().mint(minter, to, amount);
     }
 
@@ -151,8 +187,215 @@ impl PumpTokenTrait for PumpToken {
 
     fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue> {
         let key = DataKey::Allowance(AllowanceDataKey { from, spender });
-        let allowance = e.storage().temporary().get::<_, AllowanceValue>(&key);
-        allowance
+        e.storage().temporary().get::<_, AllowanceValue>(&key)
+    }
+
+    // Only minter can distribute rewards. The minter holds the reward funds,
+    // this contract only tracks each holder's share of them.
+    fn notify_reward(e: Env, amount: i128) -> Result<i128, PumpTokenError> {
+        let minter = read_minter(&e);
+        minter.require_auth();
+
+        check_nonnegative_amount(amount);
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        if !is_total_supply_seeded(&e) {
+            return Err(PumpTokenError::TotalSupplyNotSeeded);
+        }
+        let total_supply = read_total_supply(&e);
+        if read_total_weight(&e) <= 0 {
+            return Err(PumpTokenError::NoRewardRecipients);
+        }
+
+        let reward_per_token = distribute_rewards(&e, amount);
+        event::notify_reward(&e, amount, total_supply, reward_per_token);
+        Ok(reward_per_token)
+    }
+
+    // Only minter can claim, it pays the returned amount out to the user
+    fn claim_rewards(e: Env, user: Address) -> i128 {
+        let minter = read_minter(&e);
+        minter.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let amount = take_accrued_rewards(&e, user.clone());
+        if amount > 0 {
+            event::claim_rewards(&e, user, amount);
+        }
+        amount
+    }
+
+    fn accrued_rewards(e: Env, user: Address) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_accrued_rewards(&e, user)
+    }
+
+    fn reward_per_token(e: Env) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_reward_per_token(&e)
+    }
+
+    fn total_supply(e: Env) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_total_supply(&e)
+    }
+
+    // Schedules a points multiplier (in bps) for a future period. Periods
+    // cannot overlap and cannot change once started, so points already
+    // earned never move.
+    fn set_points_multiplier(
+        e: Env,
+        start: u64,
+        end: u64,
+        multiplier: i128,
+    ) -> Result<(), PumpTokenError> {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        if start < e.ledger().timestamp() || end <= start || multiplier < 0 {
+            return Err(PumpTokenError::InvalidMultiplierPeriod);
+        }
+
+        update_points_index(&e);
+        let periods = read_multiplier_periods(&e);
+        if periods.len() >= MAX_MULTIPLIER_PERIODS {
+            return Err(PumpTokenError::TooManyMultiplierPeriods);
+        }
+
+        // Keep the periods ordered by start
+        let mut index = periods.len();
+        for (i, period) in periods.iter().enumerate() {
+            if start < period.end && period.start < end {
+                return Err(PumpTokenError::InvalidMultiplierPeriod);
+            }
+            if index == periods.len() && end <= period.start {
+                index = i as u32;
+            }
+        }
+
+        let mut periods = periods;
+        periods.insert(
+            index,
+            MultiplierPeriod {
+                start,
+                end,
+                multiplier,
+            },
+        );
+        write_multiplier_periods(&e, &periods);
+
+        event::set_points_multiplier(&e, start, end, multiplier);
+        Ok(())
+    }
+
+    fn remove_points_multiplier(e: Env, start: u64) -> Result<(), PumpTokenError> {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let mut periods = read_multiplier_periods(&e);
+        let index = periods
+            .iter()
+            .position(|period| period.start == start)
+            .ok_or(PumpTokenError::MultiplierPeriodNotFound)?;
+        if start <= e.ledger().timestamp() {
+            return Err(PumpTokenError::InvalidMultiplierPeriod);
+        }
+
+        periods.remove(index as u32);
+        write_multiplier_periods(&e, &periods);
+
+        event::remove_points_multiplier(&e, start);
+        Ok(())
+    }
+
+    fn get_points_multipliers(e: Env) -> Vec<MultiplierPeriod> {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_multiplier_periods(&e)
+    }
+
+    // Time-weighted pumpBTC holdings of `user`: pumpBTC units held per second,
+    // scaled by the multiplier in force
+    fn get_points(e: Env, user: Address) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_points(&e, user)
+    }
+
+    fn get_total_points(e: Env) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_total_points(&e)
+    }
+
+    // Only minter can lock, for example while the user's stake is committed
+    // for a fixed term. `boost` earns rewards and points on top of `amount`.
+    fn lock(e: Env, user: Address, amount: i128, boost: i128) {
+        let minter = read_minter(&e);
+        minter.require_auth();
+
+        check_nonnegative_amount(amount);
+        check_nonnegative_amount(boost);
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        lock_balance(&e, user.clone(), amount, boost);
+        event::lock(&e, user, amount, boost);
+    }
+
+    // Only minter can unlock
+    fn unlock(e: Env, user: Address, amount: i128, boost: i128) {
+        let minter = read_minter(&e);
+        minter.require_auth();
+
+        check_nonnegative_amount(amount);
+        check_nonnegative_amount(boost);
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        unlock_balance(&e, user.clone(), amount, boost);
+        event::unlock(&e, user, amount, boost);
+    }
+
+    fn locked_balance(e: Env, user: Address) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_locked_balance(&e, user)
+    }
+
+    fn get_boost(e: Env, user: Address) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_boost(&e, user)
     }
 }
 
@@ -228,6 +471,7 @@ impl token::Interface for PumpToken {
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
         spend_balance(&e, from.clone(), amount);
+        write_total_supply(&e, read_total_supply(&e) - amount);
         TokenUtils::new(&e).events().burn(from, amount);
     }
 
@@ -244,6 +488,7 @@ impl token::Interface for PumpToken {
 
         spend_allowance(&e, from.clone(), spender, amount);
         spend_balance(&e, from.clone(), amount);
+        write_total_supply(&e, read_total_supply(&e) - amount);
         TokenUtils::new(&e).events().burn(from, amount)
     }
 
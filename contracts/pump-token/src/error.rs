@@ -5,4 +5,9 @@ use soroban_sdk::{self, contracterror};
 #[repr(u32)]
 pub enum PumpTokenError {
     NoPendingAdminTransfer = 0,
+    NoRewardRecipients = 1,
+    InvalidMultiplierPeriod = 2,
+    MultiplierPeriodNotFound = 3,
+    TooManyMultiplierPeriods = 4,
+    TotalSupplyNotSeeded = 5,
 }
@@ -43,3 +43,92 @@ pub(crate) fn renounce_admin(e: &Env, admin: Address) {
     e.events()
         .publish(("PumpToken", symbol_short!("ren_admin")), event);
 }
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NotifyRewardEvent {
+    pub amount: i128,
+    pub total_supply: i128,
+    pub reward_per_token: i128,
+}
+
+pub(crate) fn notify_reward(e: &Env, amount: i128, total_supply: i128, reward_per_token: i128) {
+    let event: NotifyRewardEvent = NotifyRewardEvent {
+        amount,
+        total_supply,
+        reward_per_token,
+    };
+    e.events()
+        .publish(("PumpToken", symbol_short!("reward")), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimRewardsEvent {
+    pub user: Address,
+    pub amount: i128,
+}
+
+pub(crate) fn claim_rewards(e: &Env, user: Address, amount: i128) {
+    let event: ClaimRewardsEvent = ClaimRewardsEvent { user, amount };
+    e.events()
+        .publish(("PumpToken", symbol_short!("claim_rwd")), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetPointsMultiplierEvent {
+    pub start: u64,
+    pub end: u64,
+    pub multiplier: i128,
+}
+
+pub(crate) fn set_points_multiplier(e: &Env, start: u64, end: u64, multiplier: i128) {
+    let event: SetPointsMultiplierEvent = SetPointsMultiplierEvent {
+        start,
+        end,
+        multiplier,
+    };
+    e.events()
+        .publish(("PumpToken", symbol_short!("set_mult")), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemovePointsMultiplierEvent {
+    pub start: u64,
+}
+
+pub(crate) fn remove_points_multiplier(e: &Env, start: u64) {
+    let event: RemovePointsMultiplierEvent = RemovePointsMultiplierEvent { start };
+    e.events()
+        .publish(("PumpToken", symbol_short!("rem_mult")), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockEvent {
+    pub user: Address,
+    pub amount: i128,
+    pub boost: i128,
+}
+
+pub(crate) fn lock(e: &Env, user: Address, amount: i128, boost: i128) {
+    let event: LockEvent = LockEvent {
+        user,
+        amount,
+        boost,
+    };
+    e.events()
+        .publish(("PumpToken", symbol_short!("lock")), event);
+}
+
+pub(crate) fn unlock(e: &Env, user: Address, amount: i128, boost: i128) {
+    let event: LockEvent = LockEvent {
+        user,
+        amount,
+        boost,
+    };
+    e.events()
+        .publish(("PumpToken", symbol_short!("unlock")), event);
+}
@@ -6,8 +6,11 @@ mod balance;
 mod contract;
 mod error;
 mod event;
+mod lock;
 mod metadata;
 mod minter;
+mod points;
+mod rewards;
 mod storage_types;
 mod test;
 
@@ -0,0 +1,68 @@
+use soroban_sdk::{Address, Env};
+
+use crate::balance::{read_balance, read_holder_amount, write_holder_amount};
+use crate::points::checkpoint_points;
+use crate::rewards::{checkpoint_rewards, read_total_supply};
+use crate::storage_types::DataKey;
+
+// Locked balance cannot be transferred or burned until the minter unlocks it.
+// A lock can carry a boost, which counts as extra balance for rewards and
+// points while the lock lasts.
+
+pub fn read_locked_balance(e: &Env, addr: Address) -> i128 {
+    read_holder_amount(e, &DataKey::LockedBalance(addr))
+}
+
+pub fn read_boost(e: &Env, addr: Address) -> i128 {
+    read_holder_amount(e, &DataKey::Boost(addr))
+}
+
+pub fn read_total_boost(e: &Env) -> i128 {
+    let key = DataKey::TotalBoost;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+fn write_total_boost(e: &Env, amount: i128) {
+    let key = DataKey::TotalBoost;
+    e.storage().instance().set(&key, &amount);
+}
+
+// Balance that earns rewards and points
+pub fn read_weight(e: &Env, addr: Address) -> i128 {
+    read_balance(e, addr.clone()) + read_boost(e, addr)
+}
+
+pub fn read_total_weight(e: &Env) -> i128 {
+    read_total_supply(e) + read_total_boost(e)
+}
+
+pub fn lock_balance(e: &Env, addr: Address, amount: i128, boost: i128) {
+    let locked = read_locked_balance(e, addr.clone());
+    if read_balance(e, addr.clone()) - locked < amount {
+        panic!("insufficient balance");
+    }
+
+    checkpoint_rewards(e, addr.clone());
+    checkpoint_points(e, addr.clone());
+    write_holder_amount(e, &DataKey::LockedBalance(addr.clone()), locked + amount);
+    write_holder_amount(
+        e,
+        &DataKey::Boost(addr.clone()),
+        read_boost(e, addr) + boost,
+    );
+    write_total_boost(e, read_total_boost(e) + boost);
+}
+
+pub fn unlock_balance(e: &Env, addr: Address, amount: i128, boost: i128) {
+    let locked = read_locked_balance(e, addr.clone());
+    let current_boost = read_boost(e, addr.clone());
+    if locked < amount || current_boost < boost {
+        panic!("insufficient locked balance");
+    }
+
+    checkpoint_rewards(e, addr.clone());
+    checkpoint_points(e, addr.clone());
+    write_holder_amount(e, &DataKey::LockedBalance(addr.clone()), locked - amount);
+    write_holder_amount(e, &DataKey::Boost(addr), current_boost - boost);
+    write_total_boost(e, read_total_boost(e) - boost);
+}
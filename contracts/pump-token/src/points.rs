@@ -0,0 +1,122 @@
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::balance::{read_holder_amount, write_holder_amount};
+use crate::lock::{read_total_weight, read_weight};
+use crate::storage_types::DataKey;
+
+// Multipliers are in bps, outside any multiplier period points accrue at 1x
+pub(crate) const BASE_MULTIPLIER: i128 = 10000;
+
+// Most multiplier periods that can be scheduled ahead at once
+pub(crate) const MAX_MULTIPLIER_PERIODS: u32 = 10;
+
+// Points accrue at `multiplier` bps from `start` until `end`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct MultiplierPeriod {
+    pub start: u64,
+    pub end: u64,
+    pub multiplier: i128,
+}
+
+pub fn read_multiplier_periods(e: &Env) -> Vec<MultiplierPeriod> {
+    let key = DataKey::MultiplierPeriods;
+    e.storage().instance().get(&key).unwrap_or(Vec::new(e))
+}
+
+pub fn write_multiplier_periods(e: &Env, periods: &Vec<MultiplierPeriod>) {
+    let key = DataKey::MultiplierPeriods;
+    e.storage().instance().set(&key, periods);
+}
+
+fn read_points_index(e: &Env) -> i128 {
+    let key = DataKey::PointsIndex;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+fn read_total_points_checkpoint(e: &Env) -> i128 {
+    let key = DataKey::TotalPoints;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+// Points start accruing the first time the index is updated
+fn read_points_updated_at(e: &Env) -> u64 {
+    let key = DataKey::PointsUpdatedAt;
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(e.ledger().timestamp())
+}
+
+// Multiplier-weighted seconds between `from` and `to`, in bps
+fn index_increase(e: &Env, from: u64, to: u64) -> i128 {
+    let mut increase = (to - from) as i128 * BASE_MULTIPLIER;
+    for period in read_multiplier_periods(e).iter() {
+        let start = period.start.max(from);
+        let end = period.end.min(to);
+        if start < end {
+            increase += (end - start) as i128 * (period.multiplier - BASE_MULTIPLIER);
+        }
+    }
+    increase
+}
+
+// Points index and total points as of now, without writing them
+fn current_points(e: &Env) -> (i128, i128) {
+    let increase = index_increase(e, read_points_updated_at(e), e.ledger().timestamp());
+    let index = read_points_index(e) + increase;
+    let total_points =
+        read_total_points_checkpoint(e) + read_total_weight(e) * increase / BASE_MULTIPLIER;
+    (index, total_points)
+}
+
+// Brings the points index up to now and drops the periods that have ended
+pub fn update_points_index(e: &Env) -> i128 {
+    let now = e.ledger().timestamp();
+    let (index, total_points) = current_points(e);
+    e.storage().instance().set(&DataKey::PointsIndex, &index);
+    e.storage()
+        .instance()
+        .set(&DataKey::TotalPoints, &total_points);
+    e.storage().instance().set(&DataKey::PointsUpdatedAt, &now);
+
+    let periods = read_multiplier_periods(e);
+    let mut active = Vec::new(e);
+    for period in periods.iter() {
+        if period.end > now {
+            active.push_back(period);
+        }
+    }
+    if active.len() != periods.len() {
+        write_multiplier_periods(e, &active);
+    }
+    index
+}
+
+// Points of `addr` so far, in pumpBTC units held per second at 1x. Boosts
+// count as held balance.
+pub fn read_points(e: &Env, addr: Address) -> i128 {
+    let (index, _) = current_points(e);
+    let points = read_holder_amount(e, &DataKey::Points(addr.clone()));
+    let paid = read_holder_amount(e, &DataKey::PointsIndexPaid(addr.clone()));
+    let weight = read_weight(e, addr);
+    points + weight * (index - paid) / BASE_MULTIPLIER
+}
+
+pub fn read_total_points(e: &Env) -> i128 {
+    current_points(e).1
+}
+
+// Books the points earned on the current balance of `addr`. Must run before
+// every balance, supply and boost change, like `checkpoint_rewards`.
+pub fn checkpoint_points(e: &Env, addr: Address) {
+    let index = update_points_index(e);
+    let paid_key = DataKey::PointsIndexPaid(addr.clone());
+    if read_holder_amount(e, &paid_key) == index {
+        return;
+    }
+
+    let points = read_points(e, addr.clone());
+    write_holder_amount(e, &DataKey::Points(addr), points);
+    write_holder_amount(e, &paid_key, index);
+}
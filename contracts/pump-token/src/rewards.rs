@@ -0,0 +1,84 @@
+use soroban_sdk::{Address, Env};
+
+use crate::balance::{read_holder_amount, write_holder_amount};
+use crate::lock::{read_total_weight, read_weight};
+use crate::storage_types::DataKey;
+
+// Rewards per token are scaled by this factor so that small rewards spread
+// over a large supply do not round away
+pub(crate) const REWARD_PRECISION: i128 = 1_000_000_000_000_000_000;
+
+// The total supply is only tracked from `initialize` on. A token whose
+// supply was not tracked from its first mint would spread rewards and points
+// over too small a supply, so rewards stay disabled until it is seeded.
+pub fn is_total_supply_seeded(e: &Env) -> bool {
+    let key = DataKey::TotalSupplySeeded;
+    e.storage().instance().get(&key).unwrap_or(false)
+}
+
+pub fn write_total_supply_seeded(e: &Env) {
+    let key = DataKey::TotalSupplySeeded;
+    e.storage().instance().set(&key, &true);
+}
+
+pub fn read_total_supply(e: &Env) -> i128 {
+    let key = DataKey::TotalSupply;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+pub fn write_total_supply(e: &Env, amount: i128) {
+    let key = DataKey::TotalSupply;
+    e.storage().instance().set(&key, &amount);
+}
+
+pub fn read_reward_per_token(e: &Env) -> i128 {
+    let key = DataKey::RewardPerToken;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+fn write_reward_per_token(e: &Env, reward_per_token: i128) {
+    let key = DataKey::RewardPerToken;
+    e.storage().instance().set(&key, &reward_per_token);
+}
+
+// Rewards earned by `addr` so far, including those not yet checkpointed
+pub fn read_accrued_rewards(e: &Env, addr: Address) -> i128 {
+    let accrued = read_holder_amount(e, &DataKey::AccruedRewards(addr.clone()));
+    let paid = read_holder_amount(e, &DataKey::RewardPerTokenPaid(addr.clone()));
+    let weight = read_weight(e, addr);
+    accrued + weight * (read_reward_per_token(e) - paid) / REWARD_PRECISION
+}
+
+// Books the rewards earned on the current balance of `addr`. Must run before
+// every balance or boost change, so that each holder earns exactly on what
+// they held.
+pub fn checkpoint_rewards(e: &Env, addr: Address) {
+    let reward_per_token = read_reward_per_token(e);
+    let paid_key = DataKey::RewardPerTokenPaid(addr.clone());
+    if read_holder_amount(e, &paid_key) == reward_per_token {
+        return;
+    }
+
+    let accrued = read_accrued_rewards(e, addr.clone());
+    write_holder_amount(e, &DataKey::AccruedRewards(addr), accrued);
+    write_holder_amount(e, &paid_key, reward_per_token);
+}
+
+// Spreads `amount` over the current supply and boosts, and returns the new
+// reward per token. Rounding dust is never distributed.
+pub fn distribute_rewards(e: &Env, amount: i128) -> i128 {
+    let reward_per_token =
+        read_reward_per_token(e) + amount * REWARD_PRECISION / read_total_weight(e);
+    write_reward_per_token(e, reward_per_token);
+    reward_per_token
+}
+
+pub fn take_accrued_rewards(e: &Env, addr: Address) -> i128 {
+    checkpoint_rewards(e, addr.clone());
+    let key = DataKey::AccruedRewards(addr);
+    let accrued = read_holder_amount(e, &key);
+    if accrued > 0 {
+        write_holder_amount(e, &key, 0);
+    }
+    accrued
+}
@@ -30,4 +30,18 @@ pub enum DataKey {
     Admin,
     PendingAdmin,
     Minter,
+    TotalSupply,
+    RewardPerToken,
+    RewardPerTokenPaid(Address),
+    AccruedRewards(Address),
+    PointsIndex,
+    PointsUpdatedAt,
+    TotalPoints,
+    MultiplierPeriods,
+    PointsIndexPaid(Address),
+    Points(Address),
+    LockedBalance(Address),
+    Boost(Address),
+    TotalBoost,
+    TotalSupplySeeded,
 }
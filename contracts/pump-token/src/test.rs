@@ -1,10 +1,12 @@
 #![cfg(test)]
 extern crate std;
 
-use crate::{contract::PumpToken, PumpTokenClient};
+use crate::{
+    contract::PumpToken, error::PumpTokenError, points::MultiplierPeriod, PumpTokenClient,
+};
 use soroban_sdk::{
     symbol_short,
-    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Ledger},
     Address, Env, IntoVal, Symbol,
 };
 
@@ -616,3 +618,247 @@ fn test_complex_admin_transfer_scenario() {
     token.set_minter(&new_minter);
     assert_eq!(token.get_minter(), new_minter);
 }
+
+#[test]
+fn test_reward_distribution() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+
+    // Nothing to distribute to before the first mint
+    assert_eq!(
+        token.try_notify_reward(&100),
+        Err(Ok(PumpTokenError::NoRewardRecipients))
+    );
+
+    token.mint(&user1, &3000);
+    token.mint(&user2, &1000);
+    assert_eq!(token.total_supply(), 4000);
+
+    token.notify_reward(&400);
+    assert_eq!(token.accrued_rewards(&user1), 300);
+    assert_eq!(token.accrued_rewards(&user2), 100);
+
+    // Transfers checkpoint both sides, rewards follow the new balances
+    token.transfer(&user1, &user2, &2000);
+    token.notify_reward(&400);
+    assert_eq!(token.accrued_rewards(&user1), 400);
+    assert_eq!(token.accrued_rewards(&user2), 400);
+
+    // Burned tokens stop earning
+    token.burn(&user2, &3000);
+    assert_eq!(token.total_supply(), 1000);
+    token.notify_reward(&100);
+    assert_eq!(token.accrued_rewards(&user1), 500);
+    assert_eq!(token.accrued_rewards(&user2), 400);
+
+    assert_eq!(token.claim_rewards(&user1), 500);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            minter.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "claim_rewards"),
+                    (&user1,).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(token.accrued_rewards(&user1), 0);
+    assert_eq!(token.claim_rewards(&user1), 0);
+    assert_eq!(token.claim_rewards(&user2), 400);
+}
+
+#[test]
+fn test_rewards_need_a_seeded_supply() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+    token.mint(&user, &1000);
+
+    // A token whose supply was not tracked since its first mint
+    e.as_contract(&token.address, || {
+        e.storage()
+            .instance()
+            .remove(&crate::storage_types::DataKey::TotalSupplySeeded);
+    });
+    assert_eq!(
+        token.try_notify_reward(&100),
+        Err(Ok(PumpTokenError::TotalSupplyNotSeeded))
+    );
+    assert_eq!(token.reward_per_token(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_non_minter_cannot_notify_reward() {
+    let e = Env::default();
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+
+    token.notify_reward(&100);
+}
+
+#[test]
+fn test_points_accrual() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(1000);
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+
+    token.mint(&user1, &100);
+    e.ledger().set_timestamp(1010);
+    assert_eq!(token.get_points(&user1), 1000);
+
+    // Double points from 1020 to 1030
+    token.mint(&user2, &300);
+    token.set_points_multiplier(&1020, &1030, &20000);
+    e.ledger().set_timestamp(1040);
+    assert_eq!(token.get_points(&user1), 5000);
+    assert_eq!(token.get_points(&user2), 12000);
+    assert_eq!(token.get_total_points(), 17000);
+
+    // Balance changes checkpoint both sides
+    token.transfer(&user2, &user1, &100);
+    e.ledger().set_timestamp(1050);
+    assert_eq!(token.get_points(&user1), 7000);
+    assert_eq!(token.get_points(&user2), 14000);
+    assert_eq!(token.get_points_multipliers().len(), 0);
+
+    // Burned tokens stop earning
+    token.burn(&user1, &200);
+    e.ledger().set_timestamp(1060);
+    assert_eq!(token.get_points(&user1), 7000);
+    assert_eq!(token.get_points(&user2), 16000);
+    assert_eq!(token.get_total_points(), 23000);
+}
+
+#[test]
+fn test_points_multiplier_periods() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(100);
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+
+    // Periods must lie in the future, be non-empty and not overlap
+    for (start, end, multiplier) in [(50, 150, 20000), (200, 200, 20000), (200, 300, -1)] {
+        assert_eq!(
+            token.try_set_points_multiplier(&start, &end, &multiplier),
+            Err(Ok(PumpTokenError::InvalidMultiplierPeriod))
+        );
+    }
+    token.set_points_multiplier(&200, &300, &20000);
+    token.set_points_multiplier(&300, &400, &0);
+    assert_eq!(
+        token.try_set_points_multiplier(&250, &350, &15000),
+        Err(Ok(PumpTokenError::InvalidMultiplierPeriod))
+    );
+
+    token.set_points_multiplier(&100, &200, &15000);
+    let periods = token.get_points_multipliers();
+    assert_eq!(
+        periods.get(0).unwrap(),
+        MultiplierPeriod {
+            start: 100,
+            end: 200,
+            multiplier: 15000,
+        }
+    );
+    assert_eq!(periods.get(2).unwrap().start, 300);
+
+    // Started periods cannot be removed
+    assert_eq!(
+        token.try_remove_points_multiplier(&500),
+        Err(Ok(PumpTokenError::MultiplierPeriodNotFound))
+    );
+    e.ledger().set_timestamp(150);
+    assert_eq!(
+        token.try_remove_points_multiplier(&100),
+        Err(Ok(PumpTokenError::InvalidMultiplierPeriod))
+    );
+    token.remove_points_multiplier(&300);
+    assert_eq!(token.get_points_multipliers().len(), 2);
+
+    for start in 0..8 {
+        token.set_points_multiplier(&(1000 + start), &(1001 + start), &20000);
+    }
+    assert_eq!(
+        token.try_set_points_multiplier(&2000, &2001, &20000),
+        Err(Ok(PumpTokenError::TooManyMultiplierPeriods))
+    );
+}
+
+#[test]
+fn test_lock_boosts_rewards_and_points() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(1000);
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+
+    token.mint(&user1, &100);
+    token.mint(&user2, &100);
+
+    // user1 locks 60 with a boost of 100, doubling their weight
+    token.lock(&user1, &60, &100);
+    assert_eq!(token.locked_balance(&user1), 60);
+    assert_eq!(token.get_boost(&user1), 100);
+    token.transfer(&user1, &user2, &40);
+    token.transfer(&user2, &user1, &40);
+
+    token.notify_reward(&300);
+    assert_eq!(token.accrued_rewards(&user1), 200);
+    assert_eq!(token.accrued_rewards(&user2), 100);
+
+    e.ledger().set_timestamp(1010);
+    assert_eq!(token.get_points(&user1), 2000);
+    assert_eq!(token.get_points(&user2), 1000);
+    assert_eq!(token.get_total_points(), 3000);
+
+    token.unlock(&user1, &60, &100);
+    assert_eq!(token.locked_balance(&user1), 0);
+    token.burn(&user1, &100);
+    assert_eq!(token.balance(&user1), 0);
+}
+
+#[test]
+#[should_panic(expected = "balance is locked")]
+fn test_transfer_locked_balance() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin, &minter);
+
+    token.mint(&user1, &100);
+    token.lock(&user1, &60, &0);
+    token.transfer(&user1, &user2, &41);
+}
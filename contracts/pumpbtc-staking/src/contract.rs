@@ -4,12 +4,28 @@ use crate::auth::{
 };
 use crate::error::PumpBTCStakingError;
 use crate::event;
+use crate::intent::{verify_intent, IntentAction, StakingIntent};
+use crate::lock::{LockPosition, LockTier};
 use crate::math::{
-    adjust_amount, check_nonnegative_amount, safe_add, safe_div, safe_mul, safe_sub,
+    adjust_amount, adjust_amount_exact, apply_claim_ratio, btc_to_pump, check_nonnegative_amount,
+    instant_unstake_fee, pump_to_btc, reserved_liquidity, round_to_pump_unit, safe_add, safe_div,
+    safe_mul, safe_sub, scale_total, to_pump_amount_exact, Rounding,
+};
+use crate::migration::migrate;
+use crate::solvency::{
+    daily_amounts, funding_schedule, funding_shortfall, solvency_report, SolvencyReport,
 };
 use crate::storage::*;
-use crate::utils::{check_not_paused, check_unstake_allowed, extend_instance_ttl, get_date_slot};
-use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, IntoVal, Symbol};
+use crate::tokens::{
+    burn_pump, claim_pump_rewards, lock_pump, mint_pump, notify_pump_reward, pump_accrued_rewards,
+    transfer_asset, transfer_asset_from, transfer_pump_from, transfer_token, unlock_pump,
+};
+use crate::utils::{
+    check_not_paused, check_operator_not_paused, check_schema_version, check_unstake_allowed,
+    consume_withdraw_allowance, enforce_invariants, extend_instance_ttl, get_date_slot,
+    get_maturity_day, remaining_withdraw_allowance,
+};
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, Vec};
 
 pub trait PumpBTCStakingContractTrait {
     fn initialize(
@@ -18,12 +34,23 @@ pub trait PumpBTCStakingContractTrait {
         pump_token_address: Address,
         asset_token_address: Address,
     ) -> Result<(), PumpBTCStakingError>;
-    fn upgrade(e: Env, hash: BytesN<32>);
+    fn initialize_sac_pump(
+        e: Env,
+        admin: Address,
+        pump_token_address: Address,
+        asset_token_address: Address,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn announce_upgrade(e: Env, hash: BytesN<32>) -> Result<(), PumpBTCStakingError>;
+    fn cancel_upgrade(e: Env) -> Result<(), PumpBTCStakingError>;
+    fn execute_upgrade(e: Env) -> Result<(), PumpBTCStakingError>;
+    fn migrate(e: Env, from_version: u32) -> Result<u32, PumpBTCStakingError>;
 
     fn transfer_admin(e: Env, new_admin: Address) -> Result<(), PumpBTCStakingError>;
     fn accept_admin(e: Env) -> Result<(), PumpBTCStakingError>;
     fn renounce_admin(e: Env) -> Result<(), PumpBTCStakingError>;
     fn get_pending_admin(e: Env) -> Option<Address>;
+    fn version(e: Env) -> u32;
+    fn get_pending_upgrade(e: Env) -> Option<PendingUpgrade>;
 
     fn set_stake_asset_cap(e: Env, new_total_staking_cap: i128) -> Result<(), PumpBTCStakingError>;
     fn set_normal_unstake_fee(
@@ -34,20 +61,112 @@ pub trait PumpBTCStakingContractTrait {
         e: Env,
         new_instant_unstake_fee: i128,
     ) -> Result<(), PumpBTCStakingError>;
+    fn set_instant_fee_curve(
+        e: Env,
+        base_fee: i128,
+        max_fee: i128,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn remove_instant_fee_curve(e: Env) -> Result<(), PumpBTCStakingError>;
+    fn set_liquidity_buffer(
+        e: Env,
+        buffer_amount: i128,
+        buffer_bps: i128,
+    ) -> Result<(), PumpBTCStakingError>;
     fn set_operator(e: Env, new_operator: Address) -> Result<(), PumpBTCStakingError>;
     fn set_only_allow_stake(e: Env, only_allow_stake: bool) -> Result<(), PumpBTCStakingError>;
+    fn set_lock_penalty(e: Env, new_lock_penalty: i128) -> Result<(), PumpBTCStakingError>;
+    fn set_treasury(e: Env, new_treasury: Address) -> Result<(), PumpBTCStakingError>;
+    fn set_fee_splits(e: Env, fee_splits: Vec<FeeSplit>) -> Result<(), PumpBTCStakingError>;
     fn collect_fee(e: Env) -> Result<(), PumpBTCStakingError>;
-    fn withdraw(e: Env) -> Result<(), PumpBTCStakingError>;
+    fn reconcile(e: Env) -> Result<i128, PumpBTCStakingError>;
+    fn rescue_tokens(
+        e: Env,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn set_withdraw_destination(
+        e: Env,
+        destination: Address,
+        allowed: bool,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn set_withdraw_limit(
+        e: Env,
+        withdraw_limit: i128,
+        withdraw_window: u64,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn unpause_operator(e: Env) -> Result<(), PumpBTCStakingError>;
+    fn set_rate_oracle(e: Env, oracle: Address) -> Result<(), PumpBTCStakingError>;
+    fn set_exchange_rate_bounds(
+        e: Env,
+        max_change_bps: i128,
+        min_update_interval: u64,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn update_exchange_rate(
+        e: Env,
+        updater: Address,
+        new_rate: i128,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn withdraw(e: Env) -> Result<i128, PumpBTCStakingError>;
+    fn withdraw_amount(
+        e: Env,
+        amount: i128,
+        destination: Address,
+    ) -> Result<i128, PumpBTCStakingError>;
     fn deposit(e: Env, amount: i128) -> Result<(), PumpBTCStakingError>;
-    fn withdraw_and_deposit(e: Env, deposit_amount: i128) -> Result<(), PumpBTCStakingError>;
+    fn withdraw_and_deposit(e: Env, deposit_amount: i128) -> Result<i128, PumpBTCStakingError>;
+    fn deposit_rewards(e: Env, amount: i128) -> Result<(), PumpBTCStakingError>;
+    fn deposit_lock_penalty(e: Env) -> Result<i128, PumpBTCStakingError>;
+    fn report_loss(e: Env, reporter: Address, amount: i128) -> Result<(), PumpBTCStakingError>;
     fn stake(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError>;
+    fn stake_from_allowance(e: Env, user: Address, amount: i128)
+        -> Result<(), PumpBTCStakingError>;
     fn unstake_request(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError>;
     fn claim_slot(e: Env, user: Address, slot: u32) -> Result<(), PumpBTCStakingError>;
     fn claim_all(e: Env, user: Address) -> Result<(), PumpBTCStakingError>;
+    fn claim_rewards(e: Env, user: Address) -> Result<i128, PumpBTCStakingError>;
+    fn stake_locked(
+        e: Env,
+        user: Address,
+        amount: i128,
+        tier: LockTier,
+    ) -> Result<u32, PumpBTCStakingError>;
+    fn unlock(e: Env, user: Address, lock_id: u32) -> Result<i128, PumpBTCStakingError>;
     fn unstake_instant(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError>;
+    fn stake_native(e: Env, user: Address, asset_amount: i128)
+        -> Result<i128, PumpBTCStakingError>;
+    fn unstake_instant_native(
+        e: Env,
+        user: Address,
+        asset_amount: i128,
+    ) -> Result<i128, PumpBTCStakingError>;
+    fn set_keeper_tip(
+        e: Env,
+        user: Address,
+        opted_in: bool,
+        keeper_tip: i128,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn claim_for(e: Env, keeper: Address, users: Vec<Address>) -> Result<u32, PumpBTCStakingError>;
+    fn set_intent_signer(
+        e: Env,
+        user: Address,
+        public_key: BytesN<32>,
+    ) -> Result<(), PumpBTCStakingError>;
+    fn submit_intent(
+        e: Env,
+        relayer: Address,
+        intent: StakingIntent,
+        signature: BytesN<64>,
+    ) -> Result<(), PumpBTCStakingError>;
 
     fn get_max_date_slot(e: Env) -> u32;
     fn get_pump_token(e: Env) -> Address;
+    fn get_pump_token_kind(e: Env) -> PumpTokenKind;
+    fn get_exchange_rate(e: Env) -> i128;
+    fn get_claim_ratio(e: Env) -> i128;
+    fn get_exchange_rate_bounds(e: Env) -> ExchangeRateBounds;
+    fn get_rate_oracle(e: Env) -> Option<Address>;
+    fn get_exchange_rate_history(e: Env, limit: u32) -> Vec<ExchangeRateUpdate>;
     fn get_asset_token(e: Env) -> Address;
     fn get_asset_decimal(e: Env) -> u32;
     fn get_total_staking_amount(e: Env) -> i128;
@@ -55,17 +174,50 @@ pub trait PumpBTCStakingContractTrait {
     fn get_total_requested_amount(e: Env) -> i128;
     fn get_total_claimable_amount(e: Env) -> i128;
     fn get_pending_stake_amount(e: Env) -> i128;
+    fn get_liquidity_buffer(e: Env) -> (i128, i128);
+    fn get_reserved_liquidity(e: Env) -> Result<i128, PumpBTCStakingError>;
+    fn get_withdrawable_amount(e: Env) -> Result<i128, PumpBTCStakingError>;
     fn get_collected_fee(e: Env) -> i128;
+    fn get_unallocated_amount(e: Env) -> i128;
+    fn get_reward_reserve(e: Env) -> i128;
+    fn get_accrued_rewards(e: Env, user: Address) -> i128;
+    fn check_solvency(e: Env) -> Result<SolvencyReport, PumpBTCStakingError>;
+    fn get_funding_schedule(e: Env, days: u32)
+        -> Result<Vec<DailyObligation>, PumpBTCStakingError>;
+    fn get_funding_shortfall(e: Env) -> Result<i128, PumpBTCStakingError>;
+    fn get_collected_normal_fee(e: Env) -> i128;
+    fn get_collected_instant_fee(e: Env) -> i128;
+    fn get_lifetime_normal_fee(e: Env) -> i128;
+    fn get_lifetime_instant_fee(e: Env) -> i128;
+    fn get_collected_lock_penalty(e: Env) -> i128;
+    fn get_lifetime_lock_penalty(e: Env) -> i128;
     fn get_operator(e: Env) -> Option<Address>;
+    fn is_withdraw_destination(e: Env, destination: Address) -> bool;
+    fn get_withdraw_limit(e: Env) -> (i128, u64);
+    fn get_remaining_withdraw_allowance(e: Env) -> Result<i128, PumpBTCStakingError>;
+    fn is_operator_paused(e: Env) -> bool;
     fn get_normal_unstake_fee(e: Env) -> i128;
     fn get_instant_unstake_fee(e: Env) -> i128;
+    fn get_instant_fee_curve(e: Env) -> Option<InstantFeeCurve>;
+    fn preview_unstake_instant(e: Env, amount: i128) -> Result<i128, PumpBTCStakingError>;
     fn get_only_allow_stake(e: Env) -> bool;
+    fn get_lock_penalty(e: Env) -> i128;
+    fn get_owed_lock_penalty(e: Env) -> i128;
+    fn get_lock_position(e: Env, user: Address, lock_id: u32) -> Option<LockPosition>;
+    fn get_lock_positions(e: Env, user: Address) -> Vec<LockPosition>;
+    fn get_next_lock_id(e: Env, user: Address) -> u32;
+    fn get_treasury(e: Env) -> Option<Address>;
+    fn get_fee_splits(e: Env) -> Vec<FeeSplit>;
     fn get_pending_unstake_time(e: Env, user: Address, slot: u32) -> u64;
     fn get_pending_unstake_amount(e: Env, user: Address, slot: u32) -> i128;
+    fn get_keeper_tip(e: Env, user: Address) -> Option<i128>;
+    fn get_intent_signer(e: Env, user: Address) -> Option<BytesN<32>>;
+    fn get_intent_nonce(e: Env, user: Address) -> u64;
 
     fn pause(e: Env) -> Result<(), PumpBTCStakingError>;
     fn unpause(e: Env) -> Result<(), PumpBTCStakingError>;
     fn is_paused(e: Env) -> bool;
+    fn get_invariant_violation(e: Env) -> Option<Invariant>;
 }
 
 #[contract]
@@ -79,40 +231,35 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         pump_token_address: Address,
         asset_token_address: Address,
     ) -> Result<(), PumpBTCStakingError> {
-        if !has_administrator(&e) {
-            write_administrator(&e, &admin);
-
-            write_pump_token_address(&e, &pump_token_address);
-            write_asset_token_address(&e, &asset_token_address);
-
-            let asset_client = token::Client::new(&e, &asset_token_address);
-            let asset_decimal = asset_client.decimals();
-            if asset_decimal < 8 {
-                return Err(PumpBTCStakingError::AssetDecimalTooSmall);
-            }
-            write_asset_decimal(&e, asset_decimal);
-
-            let pump_token_client = token::Client::new(&e, &pump_token_address);
-            let pump_token_decimal = pump_token_client.decimals();
-            if pump_token_decimal != 8 {
-                return Err(PumpBTCStakingError::InvalidPumpTokenDecimal);
-            }
-
-            write_normal_unstake_fee(&e, 0);
-            write_instant_unstake_fee(&e, 300);
-            write_only_allow_stake(&e, true);
+        initialize_internal(
+            &e,
+            &admin,
+            &pump_token_address,
+            &asset_token_address,
+            PumpTokenKind::Minter,
+        )
+    }
 
-            extend_instance_ttl(&e);
-            Ok(())
-        } else {
-            return Err(PumpBTCStakingError::AlreadyInitialized);
-        }
+    fn initialize_sac_pump(
+        e: Env,
+        admin: Address,
+        pump_token_address: Address,
+        asset_token_address: Address,
+    ) -> Result<(), PumpBTCStakingError> {
+        initialize_internal(
+            &e,
+            &admin,
+            &pump_token_address,
+            &asset_token_address,
+            PumpTokenKind::StellarAsset,
+        )
     }
 
     // ========================= Owner Functions =========================
 
     fn transfer_admin(e: Env, new_admin: Address) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -124,6 +271,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
 
     fn accept_admin(e: Env) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let pending_admin = read_pending_administrator(&e);
         if pending_admin.is_none() {
@@ -143,6 +291,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
 
     fn renounce_admin(e: Env) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -155,6 +304,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
 
     fn pause(e: Env) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -170,6 +320,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
 
     fn unpause(e: Env) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -179,37 +330,98 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         }
 
         write_paused(&e, false);
+        remove_invariant_violation(&e);
         event::unpaused(&e, admin);
         Ok(())
     }
 
-    fn upgrade(e: Env, hash: BytesN<32>) {
+    fn announce_upgrade(e: Env, hash: BytesN<32>) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        // Re-announcing replaces the pending upgrade and restarts the delay
+        let executable_after = e.ledger().timestamp() + UPGRADE_DELAY;
+        write_pending_upgrade(
+            &e,
+            &PendingUpgrade {
+                hash: hash.clone(),
+                executable_after,
+            },
+        );
+
+        event::announce_upgrade(&e, admin, hash, executable_after);
+        Ok(())
+    }
+
+    fn cancel_upgrade(e: Env) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        let pending_upgrade =
+            read_pending_upgrade(&e).ok_or(PumpBTCStakingError::NoPendingUpgrade)?;
+        remove_pending_upgrade(&e);
+
+        event::cancel_upgrade(&e, admin, pending_upgrade.hash);
+        Ok(())
+    }
+
+    fn execute_upgrade(e: Env) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        let pending_upgrade =
+            read_pending_upgrade(&e).ok_or(PumpBTCStakingError::NoPendingUpgrade)?;
+        if e.ledger().timestamp() < pending_upgrade.executable_after {
+            return Err(PumpBTCStakingError::UpgradeNotReady);
+        }
+        remove_pending_upgrade(&e);
+
+        let old_hash = read_wasm_hash(&e);
+        write_wasm_hash(&e, &pending_upgrade.hash);
+
+        e.deployer()
+            .update_current_contract_wasm(pending_upgrade.hash.clone());
+
+        event::upgrade(&e, admin, old_hash, pending_upgrade.hash);
+        Ok(())
+    }
+
+    fn migrate(e: Env, from_version: u32) -> Result<u32, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+
         let admin = read_administrator(&e);
         admin.require_auth();
 
-        e.deployer().update_current_contract_wasm(hash);
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        let to_version = migrate(&e, from_version)?;
+
+        event::migrate(&e, admin, from_version, to_version);
+        Ok(to_version)
     }
 
     fn set_stake_asset_cap(e: Env, new_total_staking_cap: i128) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
 
         check_nonnegative_amount(new_total_staking_cap)?;
-        let total_staking_amount = read_total_staking_amount(&e);
+        let total_staking_value = pump_to_btc(&e, read_total_staking_amount(&e), Rounding::Up)?;
 
-        if new_total_staking_cap >= total_staking_amount {
+        if new_total_staking_cap >= total_staking_value {
             let old_total_staking_cap = read_total_staking_cap(&e);
             write_total_staking_cap(&e, new_total_staking_cap);
 
             event::set_stake_asset_cap(&e, old_total_staking_cap, new_total_staking_cap);
             Ok(())
         } else {
-            return Err(PumpBTCStakingError::StakingCapTooSmall);
+            Err(PumpBTCStakingError::StakingCapTooSmall)
         }
     }
 
@@ -218,6 +430,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         new_normal_unstake_fee: i128,
     ) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -229,7 +442,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
             event::set_normal_unstake_fee(&e, old_normal_unstake_fee, new_normal_unstake_fee);
             Ok(())
         } else {
-            return Err(PumpBTCStakingError::FeeShouldBeBetween0And10000);
+            Err(PumpBTCStakingError::FeeShouldBeBetween0And10000)
         }
     }
 
@@ -238,6 +451,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         new_instant_unstake_fee: i128,
     ) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -249,12 +463,69 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
             event::set_instant_unstake_fee(&e, old_instant_unstake_fee, new_instant_unstake_fee);
             Ok(())
         } else {
+            Err(PumpBTCStakingError::FeeShouldBeBetween0And10000)
+        }
+    }
+
+    fn set_instant_fee_curve(
+        e: Env,
+        base_fee: i128,
+        max_fee: i128,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        if base_fee < 0 || base_fee > max_fee || max_fee >= 10000 {
+            return Err(PumpBTCStakingError::InvalidConfig);
+        }
+
+        write_instant_fee_curve(&e, &InstantFeeCurve { base_fee, max_fee });
+        event::set_instant_fee_curve(&e, true, base_fee, max_fee);
+        Ok(())
+    }
+
+    fn remove_instant_fee_curve(e: Env) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        remove_instant_fee_curve(&e);
+        event::set_instant_fee_curve(&e, false, 0, 0);
+        Ok(())
+    }
+
+    fn set_liquidity_buffer(
+        e: Env,
+        buffer_amount: i128,
+        buffer_bps: i128,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        if buffer_amount < 0 {
+            return Err(PumpBTCStakingError::NegativeAmountNotAllowed);
+        }
+        if !(0..=10000).contains(&buffer_bps) {
             return Err(PumpBTCStakingError::FeeShouldBeBetween0And10000);
         }
+
+        write_liquidity_buffer_amount(&e, buffer_amount);
+        write_liquidity_buffer_bps(&e, buffer_bps);
+        event::set_liquidity_buffer(&e, buffer_amount, buffer_bps);
+        Ok(())
     }
 
     fn set_operator(e: Env, new_operator: Address) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -269,6 +540,7 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
 
     fn set_only_allow_stake(e: Env, only_allow_stake: bool) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -279,372 +551,1034 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         Ok(())
     }
 
-    fn collect_fee(e: Env) -> Result<(), PumpBTCStakingError> {
+    fn set_lock_penalty(e: Env, new_lock_penalty: i128) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
         let admin = read_administrator(&e);
         admin.require_auth();
 
-        let fee_amount = read_collected_fee(&e);
-        if fee_amount > 0 {
-            write_collected_fee(&e, 0);
-
-            let asset_token = read_asset_token_address(&e);
+        if !(0..=10000).contains(&new_lock_penalty) {
+            return Err(PumpBTCStakingError::FeeShouldBeBetween0And10000);
+        }
 
-            let asset_client = token::Client::new(&e, &asset_token);
-            asset_client.transfer(
-                &e.current_contract_address(),
-                &admin,
-                &adjust_amount(&e, fee_amount)?,
-            );
+        let old_lock_penalty = read_lock_penalty(&e);
+        write_lock_penalty(&e, new_lock_penalty);
+        event::set_lock_penalty(&e, old_lock_penalty, new_lock_penalty);
 
-            event::collect_fee(&e, admin, fee_amount);
-            Ok(())
-        } else {
-            return Err(PumpBTCStakingError::NoFeeToCollect);
-        }
+        Ok(())
     }
 
-    // ========================= Operator Functions =========================
-
-    fn withdraw(e: Env) -> Result<(), PumpBTCStakingError> {
+    fn set_treasury(e: Env, new_treasury: Address) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        let operator = read_operator(&e);
-        if operator.is_none() {
-            return Err(PumpBTCStakingError::NoOperatorSet);
-        }
+        let admin = read_administrator(&e);
+        admin.require_auth();
 
-        let operator = operator.unwrap();
-        operator.require_auth();
+        let old_treasury = read_treasury(&e);
 
-        let old_pending_amount = read_pending_stake_amount(&e);
-        if old_pending_amount > 0 {
-            write_pending_stake_amount(&e, 0);
+        write_treasury(&e, &new_treasury);
+        event::set_treasury(&e, old_treasury, new_treasury);
 
-            let asset_token = read_asset_token_address(&e);
-            let asset_client = token::Client::new(&e, &asset_token);
-            asset_client.transfer(
-                &e.current_contract_address(),
-                &operator,
-                &adjust_amount(&e, old_pending_amount)?,
-            );
+        Ok(())
+    }
 
-            event::withdraw(&e, operator, old_pending_amount);
-            Ok(())
-        } else {
-            return Err(PumpBTCStakingError::NoPendingStakeAmount);
+    fn set_fee_splits(e: Env, fee_splits: Vec<FeeSplit>) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        // An empty table sends the whole fee to the treasury
+        if !fee_splits.is_empty() {
+            let mut total_bps: i128 = 0;
+            for fee_split in fee_splits.iter() {
+                if fee_split.bps <= 0 {
+                    return Err(PumpBTCStakingError::InvalidConfig);
+                }
+                total_bps = safe_add(total_bps, fee_split.bps)?;
+            }
+            if total_bps != 10000 {
+                return Err(PumpBTCStakingError::InvalidConfig);
+            }
         }
+
+        write_fee_splits(&e, &fee_splits);
+        event::set_fee_splits(&e, fee_splits);
+
+        Ok(())
     }
 
-    fn deposit(e: Env, amount: i128) -> Result<(), PumpBTCStakingError> {
+    // Anyone can trigger the payout, since the recipients are fixed by the admin
+    fn collect_fee(e: Env) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        let operator = read_operator(&e);
-        if operator.is_none() {
-            return Err(PumpBTCStakingError::NoOperatorSet);
+        let fee_amount = read_collected_fee(&e);
+        if fee_amount > 0 {
+            write_collected_fee(&e, 0);
+            write_bucket_collected_fee(&e, FeeBucket::NormalUnstake, 0);
+            write_bucket_collected_fee(&e, FeeBucket::InstantUnstake, 0);
+            write_bucket_collected_fee(&e, FeeBucket::LockPenalty, 0);
+
+            let fee_splits = read_fee_splits(&e);
+            if fee_splits.is_empty() {
+                let treasury = read_treasury(&e).unwrap_or(read_administrator(&e));
+                let asset_amount = adjust_amount(&e, fee_amount, Rounding::Down)?;
+                transfer_asset(&e, &e.current_contract_address(), &treasury, asset_amount)?;
+
+                event::collect_fee(&e, treasury, fee_amount, asset_amount);
+                return enforce_invariants(&e);
+            }
+
+            // The last recipient takes the rounding remainder
+            let mut remaining_fee = fee_amount;
+            let last_index = fee_splits.len() - 1;
+            for (index, fee_split) in fee_splits.iter().enumerate() {
+                let payout = if index as u32 == last_index {
+                    remaining_fee
+                } else {
+                    safe_div(safe_mul(fee_amount, fee_split.bps)?, 10000)?
+                };
+                remaining_fee = safe_sub(remaining_fee, payout)?;
+
+                let asset_amount = adjust_amount(&e, payout, Rounding::Down)?;
+                if payout > 0 {
+                    transfer_asset(
+                        &e,
+                        &e.current_contract_address(),
+                        &fee_split.recipient,
+                        asset_amount,
+                    )?;
+                }
+
+                event::collect_fee(&e, fee_split.recipient, payout, asset_amount);
+            }
+            enforce_invariants(&e)
+        } else {
+            Err(PumpBTCStakingError::NoFeeToCollect)
         }
-        let operator = operator.unwrap();
-        operator.require_auth();
+    }
 
-        check_nonnegative_amount(amount)?;
+    fn set_withdraw_destination(
+        e: Env,
+        destination: Address,
+        allowed: bool,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        let total_claimable_amount = read_total_claimable_amount(&e);
-        write_total_claimable_amount(&e, safe_add(total_claimable_amount, amount)?);
+        let admin = read_administrator(&e);
+        admin.require_auth();
 
-        let asset_token = read_asset_token_address(&e);
-        let asset_client = token::Client::new(&e, &asset_token);
-        asset_client.transfer_from(
-            &e.current_contract_address(),
-            &operator,
-            &e.current_contract_address(),
-            &adjust_amount(&e, amount)?,
-        );
+        write_withdraw_destination(&e, &destination, allowed);
+        event::set_withdraw_destination(&e, destination, allowed);
 
-        event::deposit(&e, operator, e.current_contract_address(), amount);
         Ok(())
     }
 
-    fn withdraw_and_deposit(e: Env, deposit_amount: i128) -> Result<(), PumpBTCStakingError> {
+    fn set_withdraw_limit(
+        e: Env,
+        withdraw_limit: i128,
+        withdraw_window: u64,
+    ) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        let operator = read_operator(&e);
-        if operator.is_none() {
-            return Err(PumpBTCStakingError::NoOperatorSet);
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        // A zero limit disables rate limiting
+        if withdraw_limit < 0 {
+            return Err(PumpBTCStakingError::NegativeAmountNotAllowed);
+        }
+        if withdraw_window == 0 {
+            return Err(PumpBTCStakingError::InvalidConfig);
         }
-        let operator = operator.unwrap();
-        operator.require_auth();
 
-        let asset_token = read_asset_token_address(&e);
-        let asset_client = token::Client::new(&e, &asset_token);
+        write_withdraw_limit(&e, withdraw_limit);
+        write_withdraw_window(&e, withdraw_window);
+        event::set_withdraw_limit(&e, withdraw_limit, withdraw_window);
 
-        let old_pending_stake_amount = read_pending_stake_amount(&e);
-        write_pending_stake_amount(&e, 0);
+        Ok(())
+    }
 
-        let total_claimable_amount = read_total_claimable_amount(&e);
-        write_total_claimable_amount(&e, safe_add(total_claimable_amount, deposit_amount)?);
+    fn unpause_operator(e: Env) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        event::withdraw(&e, operator.clone(), old_pending_stake_amount);
-        event::deposit(
-            &e,
-            operator.clone(),
-            e.current_contract_address(),
-            deposit_amount,
-        );
+        let admin = read_administrator(&e);
+        admin.require_auth();
 
-        if old_pending_stake_amount > deposit_amount {
-            asset_client.transfer(
-                &e.current_contract_address(),
-                &operator,
-                &adjust_amount(&e, safe_sub(old_pending_stake_amount, deposit_amount)?)?,
-            );
-        } else if old_pending_stake_amount < deposit_amount {
-            asset_client.transfer_from(
-                &e.current_contract_address(),
-                &operator,
-                &e.current_contract_address(),
-                &adjust_amount(&e, safe_sub(deposit_amount, old_pending_stake_amount)?)?,
-            );
+        if !read_operator_paused(&e) {
+            return Err(PumpBTCStakingError::OperatorFlowsNotPaused);
         }
 
+        write_operator_paused(&e, false);
+        event::operator_unpaused(&e, admin);
         Ok(())
     }
 
-    // ========================= User Functions =========================
-
-    fn stake(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+    fn set_rate_oracle(e: Env, oracle: Address) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
-        check_not_paused(&e)?;
+        check_schema_version(&e)?;
 
-        user.require_auth();
-        check_nonnegative_amount(amount)?;
+        let admin = read_administrator(&e);
+        admin.require_auth();
 
-        let total_staking_amount = read_total_staking_amount(&e);
-        let total_staking_cap = read_total_staking_cap(&e);
+        let old_oracle = read_rate_oracle(&e);
 
-        if safe_add(total_staking_amount, amount)? > total_staking_cap {
-            return Err(PumpBTCStakingError::ExceedStakingCap);
-        }
+        write_rate_oracle(&e, &oracle);
+        event::set_rate_oracle(&e, old_oracle, oracle);
 
-        write_total_staking_amount(&e, safe_add(total_staking_amount, amount)?);
-        let pending_stake_amount = read_pending_stake_amount(&e);
-        write_pending_stake_amount(&e, safe_add(pending_stake_amount, amount)?);
+        Ok(())
+    }
+
+    fn set_exchange_rate_bounds(
+        e: Env,
+        max_change_bps: i128,
+        min_update_interval: u64,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        let asset_token = read_asset_token_address(&e);
-        let asset_client = token::Client::new(&e, &asset_token);
+        let admin = read_administrator(&e);
+        admin.require_auth();
 
-        asset_client.transfer_from(
-            &e.current_contract_address(),
-            &user,
-            &e.current_contract_address(),
-            &adjust_amount(&e, amount)?,
-        );
+        if !(0..=10000).contains(&max_change_bps) {
+            return Err(PumpBTCStakingError::InvalidConfig);
+        }
 
-        // Mint pumpBTC to user
-        let pump_token = read_pump_token_address(&e);
-        e.invoke_contract::<()>(
-            &pump_token,
-            &Symbol::new(&e, "mint"),
-            (user.clone(), amount).into_val(&e),
+        write_exchange_rate_bounds(
+            &e,
+            &ExchangeRateBounds {
+                max_change_bps,
+                min_update_interval,
+            },
         );
+        event::set_rate_bounds(&e, max_change_bps, min_update_interval);
 
-        event::stake(&e, user, amount);
         Ok(())
     }
 
-    fn unstake_request(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+    // Either the operator or the rate oracle may move the rate, by at most
+    // `max_change_bps` of the current rate and once per `min_update_interval`
+    fn update_exchange_rate(
+        e: Env,
+        updater: Address,
+        new_rate: i128,
+    ) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
         check_not_paused(&e)?;
 
-        user.require_auth();
-        check_unstake_allowed(&e)?;
+        let updater_option = Some(updater.clone());
+        if updater_option != read_operator(&e) && updater_option != read_rate_oracle(&e) {
+            return Err(PumpBTCStakingError::CallerIsNotRateUpdater);
+        }
+        updater.require_auth();
 
-        check_nonnegative_amount(amount)?;
+        if new_rate <= 0 {
+            return Err(PumpBTCStakingError::InvalidExchangeRate);
+        }
 
+        let bounds = read_exchange_rate_bounds(&e);
         let block_timestamp = e.ledger().timestamp();
-        let slot = get_date_slot(block_timestamp);
+        let updated_at = read_exchange_rate_updated_at(&e);
+        if updated_at > 0 && block_timestamp < updated_at.saturating_add(bounds.min_update_interval)
+        {
+            return Err(PumpBTCStakingError::RateUpdateTooSoon);
+        }
 
-        // Check if the user can request unstake in this slot
-        let pending_unstake_time = read_pending_unstake_time(&e, &user, slot);
-        let pending_unstake_amount = read_pending_unstake_amount(&e, &user, slot);
+        let old_rate = read_exchange_rate(&e);
+        let change = safe_sub(new_rate, old_rate)?.abs();
+        if safe_mul(change, 10000)? > safe_mul(old_rate, bounds.max_change_bps)? {
+            return Err(PumpBTCStakingError::RateChangeTooLarge);
+        }
 
-        if safe_sub(block_timestamp as i128, pending_unstake_time as i128)?
-            < SECONDS_PER_DAY as i128
-            || pending_unstake_amount == 0
-        {
-            write_pending_unstake_time(&e, &user, slot, block_timestamp);
-            write_pending_unstake_amount(
-                &e,
-                &user,
-                slot,
-                safe_add(pending_unstake_amount, amount)?,
-            );
+        write_exchange_rate(&e, new_rate);
+        write_exchange_rate_updated_at(&e, block_timestamp);
+        push_exchange_rate_update(
+            &e,
+            &ExchangeRateUpdate {
+                rate: new_rate,
+                timestamp: block_timestamp,
+            },
+        );
 
-            let total_staking_amount = read_total_staking_amount(&e);
-            write_total_staking_amount(&e, safe_sub(total_staking_amount, amount)?);
+        event::update_exchange_rate(&e, updater, old_rate, new_rate);
+        Ok(())
+    }
+
+    // Records any asset balance above the tracked liabilities as unallocated
+    fn reconcile(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-            let total_requested_amount = read_total_requested_amount(&e);
-            write_total_requested_amount(&e, safe_add(total_requested_amount, amount)?);
+        let admin = read_administrator(&e);
+        admin.require_auth();
 
-            // Burn user's pumpBTC
-            let pump_token = read_pump_token_address(&e);
-            e.invoke_contract::<()>(
-                &pump_token,
-                &Symbol::new(&e, "burn"),
-                (user.clone(), amount).into_val(&e),
-            );
+        let report = solvency_report(&e)?;
+        if report.surplus <= 0 {
+            return Err(PumpBTCStakingError::InsufficientSurplus);
+        }
 
-            event::unstake_request(&e, user, amount, slot);
-            Ok(())
+        let unallocated_amount = safe_add(report.unallocated_amount, report.surplus)?;
+        write_unallocated_amount(&e, unallocated_amount);
+
+        event::reconcile(&e, admin, report.surplus, unallocated_amount);
+        enforce_invariants(&e)?;
+        Ok(report.surplus)
+    }
+
+    fn rescue_tokens(
+        e: Env,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        check_nonnegative_amount(amount)?;
+
+        // Only the asset balance above tracked liabilities may leave; the
+        // reconciled surplus is drawn down first
+        if token == read_asset_token_address(&e) {
+            let report = solvency_report(&e)?;
+            let rescuable_amount = safe_add(report.surplus, report.unallocated_amount)?;
+            if amount > rescuable_amount {
+                return Err(PumpBTCStakingError::InsufficientSurplus);
+            }
+
+            let unallocated_amount = safe_sub(
+                report.unallocated_amount,
+                amount.min(report.unallocated_amount),
+            )?;
+            write_unallocated_amount(&e, unallocated_amount);
+        }
+
+        transfer_token(&e, &token, &e.current_contract_address(), &to, amount)?;
+
+        event::rescue_tokens(&e, admin, token, to, amount);
+        enforce_invariants(&e)
+    }
+
+    // ========================= Operator Functions =========================
+
+    // Returns the amount withdrawn, 0 when the withdrawal limit blocked it and
+    // paused operator flows
+    fn withdraw(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let operator = read_operator(&e);
+        if operator.is_none() {
+            return Err(PumpBTCStakingError::NoOperatorSet);
+        }
+
+        let operator = operator.unwrap();
+        operator.require_auth();
+        check_operator_not_paused(&e)?;
+
+        // The reserved buffer stays in the contract for instant unstakes
+        let reserved_amount = reserved_liquidity(&e)?;
+        let withdraw_amount = safe_sub(read_pending_stake_amount(&e), reserved_amount)?;
+        if withdraw_amount > 0 {
+            if !consume_withdraw_allowance(&e, &operator, withdraw_amount)? {
+                return Ok(0);
+            }
+
+            write_pending_stake_amount(&e, reserved_amount);
+
+            let asset_amount = adjust_amount(&e, withdraw_amount, Rounding::Down)?;
+            transfer_asset(&e, &e.current_contract_address(), &operator, asset_amount)?;
+
+            event::withdraw(&e, operator, withdraw_amount, asset_amount);
+            enforce_invariants(&e)?;
+            Ok(withdraw_amount)
         } else {
-            return Err(PumpBTCStakingError::ClaimPreviousUnstakeFirst);
+            Err(PumpBTCStakingError::NoPendingStakeAmount)
+        }
+    }
+
+    // Returns the amount withdrawn, 0 when the withdrawal limit blocked it and
+    // paused operator flows
+    fn withdraw_amount(
+        e: Env,
+        amount: i128,
+        destination: Address,
+    ) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let operator = read_operator(&e);
+        if operator.is_none() {
+            return Err(PumpBTCStakingError::NoOperatorSet);
+        }
+        let operator = operator.unwrap();
+        operator.require_auth();
+        check_operator_not_paused(&e)?;
+
+        check_nonnegative_amount(amount)?;
+        if !is_withdraw_destination(&e, &destination) {
+            return Err(PumpBTCStakingError::DestinationNotAllowed);
+        }
+
+        let pending_stake_amount = read_pending_stake_amount(&e);
+        let withdrawable_amount = safe_sub(pending_stake_amount, reserved_liquidity(&e)?)?;
+        if amount > withdrawable_amount {
+            return Err(PumpBTCStakingError::InsufficientPendingStakeAmount);
+        }
+
+        if !consume_withdraw_allowance(&e, &operator, amount)? {
+            return Ok(0);
+        }
+
+        write_pending_stake_amount(&e, safe_sub(pending_stake_amount, amount)?);
+
+        let asset_amount = adjust_amount(&e, amount, Rounding::Down)?;
+        transfer_asset(
+            &e,
+            &e.current_contract_address(),
+            &destination,
+            asset_amount,
+        )?;
+
+        event::withdraw_amount(&e, operator, destination, amount, asset_amount);
+        enforce_invariants(&e)?;
+        Ok(amount)
+    }
+
+    fn deposit(e: Env, amount: i128) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let (operator, asset_amount) = deposit_internal(&e, amount)?;
+
+        let total_claimable_amount = read_total_claimable_amount(&e);
+        write_total_claimable_amount(&e, safe_add(total_claimable_amount, amount)?);
+
+        event::deposit(
+            &e,
+            operator,
+            e.current_contract_address(),
+            amount,
+            asset_amount,
+        );
+        enforce_invariants(&e)
+    }
+
+    // Returns the amount withdrawn, 0 when the withdrawal limit blocked the
+    // withdraw leg and paused operator flows. The deposit leg always runs.
+    fn withdraw_and_deposit(e: Env, deposit_amount: i128) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let operator = read_operator(&e);
+        if operator.is_none() {
+            return Err(PumpBTCStakingError::NoOperatorSet);
         }
+        let operator = operator.unwrap();
+        operator.require_auth();
+        check_operator_not_paused(&e)?;
+
+        check_nonnegative_amount(deposit_amount)?;
+
+        let reserved_amount = reserved_liquidity(&e)?;
+        let pending_stake_amount = read_pending_stake_amount(&e);
+        let mut withdraw_amount = safe_sub(pending_stake_amount, reserved_amount)?.max(0);
+
+        // Only the net outflow counts against the withdrawal limit
+        let net_outflow = safe_sub(withdraw_amount, deposit_amount)?;
+        if !consume_withdraw_allowance(&e, &operator, net_outflow)? {
+            withdraw_amount = 0;
+        }
+
+        let withdraw_asset_amount = adjust_amount(&e, withdraw_amount, Rounding::Down)?;
+        let deposit_asset_amount = adjust_amount_exact(&e, deposit_amount)?;
+
+        if withdraw_amount > 0 {
+            write_pending_stake_amount(&e, safe_sub(pending_stake_amount, withdraw_amount)?);
+            event::withdraw(&e, operator.clone(), withdraw_amount, withdraw_asset_amount);
+        }
+
+        let total_claimable_amount = read_total_claimable_amount(&e);
+        write_total_claimable_amount(&e, safe_add(total_claimable_amount, deposit_amount)?);
+        event::deposit(
+            &e,
+            operator.clone(),
+            e.current_contract_address(),
+            deposit_amount,
+            deposit_asset_amount,
+        );
+
+        if withdraw_asset_amount > deposit_asset_amount {
+            transfer_asset(
+                &e,
+                &e.current_contract_address(),
+                &operator,
+                safe_sub(withdraw_asset_amount, deposit_asset_amount)?,
+            )?;
+        } else if withdraw_asset_amount < deposit_asset_amount {
+            transfer_asset(
+                &e,
+                &operator,
+                &e.current_contract_address(),
+                safe_sub(deposit_asset_amount, withdraw_asset_amount)?,
+            )?;
+        }
+
+        enforce_invariants(&e)?;
+        Ok(withdraw_amount)
+    }
+
+    // Spreads a loss of `amount` BTC, such as a slashing, pro rata over the
+    // outstanding unstake requests and the BTC backing pumpBTC, pending stake
+    // included. Both the claim ratio and the exchange rate drop by the lost
+    // share of that total, outside the rate bounds. Deposits for claims shrink
+    // with the requests and the freed part goes back to pending stake. Fees and
+    // rewards are not affected.
+    fn report_loss(e: Env, reporter: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        if reporter != read_administrator(&e) && Some(reporter.clone()) != read_operator(&e) {
+            return Err(PumpBTCStakingError::CallerIsNotAdminOrOperator);
+        }
+        reporter.require_auth();
+
+        check_nonnegative_amount(amount)?;
+
+        let total_requested_amount = read_total_requested_amount(&e);
+        let total_staking_value = pump_to_btc(&e, read_total_staking_amount(&e), Rounding::Down)?;
+        let backing = safe_add(total_requested_amount, total_staking_value)?;
+        // A loss of the whole backing would leave no valid exchange rate
+        if amount >= backing {
+            return Err(PumpBTCStakingError::LossExceedsBacking);
+        }
+
+        let old_ratio = read_claim_ratio(&e);
+        let new_ratio = safe_div(safe_mul(old_ratio, safe_sub(backing, amount)?)?, backing)?;
+        let old_rate = read_exchange_rate(&e);
+        let new_rate = safe_div(safe_mul(old_rate, new_ratio)?, old_ratio)?;
+        if new_ratio <= 0 || new_rate <= 0 {
+            return Err(PumpBTCStakingError::InvalidExchangeRate);
+        }
+
+        write_claim_ratio(&e, new_ratio);
+        let new_total_requested_amount = scale_total(total_requested_amount, new_ratio, old_ratio)?;
+        write_total_requested_amount(&e, new_total_requested_amount);
+        let total_claimable_amount = read_total_claimable_amount(&e);
+        let new_total_claimable_amount = scale_total(total_claimable_amount, new_ratio, old_ratio)?
+            .min(new_total_requested_amount)
+            .min(total_claimable_amount);
+        write_total_claimable_amount(&e, new_total_claimable_amount);
+        write_pending_stake_amount(
+            &e,
+            safe_add(
+                read_pending_stake_amount(&e),
+                safe_sub(total_claimable_amount, new_total_claimable_amount)?,
+            )?,
+        );
+        // Daily amounts are scaled when next read, see `daily_amounts`
+
+        write_exchange_rate(&e, new_rate);
+        push_exchange_rate_update(
+            &e,
+            &ExchangeRateUpdate {
+                rate: new_rate,
+                timestamp: e.ledger().timestamp(),
+            },
+        );
+
+        event::report_loss(&e, reporter, amount, backing, new_ratio, old_rate, new_rate);
+        enforce_invariants(&e)
+    }
+
+    // Rewards are spread over pumpBTC holders by the pump token and stay in
+    // this contract until each holder claims them
+    fn deposit_rewards(e: Env, amount: i128) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let (operator, asset_amount) = deposit_internal(&e, amount)?;
+
+        write_reward_reserve(&e, safe_add(read_reward_reserve(&e), amount)?);
+        notify_pump_reward(&e, amount)?;
+
+        event::deposit_rewards(&e, operator, amount, asset_amount);
+        enforce_invariants(&e)
+    }
+
+    // Pays in the early unlock penalties owed so far, which then become
+    // collected fees. Returns the amount deposited.
+    fn deposit_lock_penalty(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        let operator = read_operator(&e);
+        if operator.is_none() {
+            return Err(PumpBTCStakingError::NoOperatorSet);
+        }
+        let operator = operator.unwrap();
+        operator.require_auth();
+
+        let amount = read_owed_lock_penalty(&e);
+        if amount <= 0 {
+            return Err(PumpBTCStakingError::NoOwedLockPenalty);
+        }
+        write_owed_lock_penalty(&e, 0);
+        accrue_fee(&e, FeeBucket::LockPenalty, amount)?;
+
+        let asset_amount = adjust_amount(&e, amount, Rounding::Up)?;
+        transfer_asset(&e, &operator, &e.current_contract_address(), asset_amount)?;
+
+        event::deposit_lock_penalty(&e, operator, amount, asset_amount);
+        enforce_invariants(&e)?;
+        Ok(amount)
+    }
+
+    // ========================= User Functions =========================
+
+    fn stake(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+
+        stake_internal(&e, &user, amount, false)?;
+        enforce_invariants(&e)
+    }
+
+    fn stake_from_allowance(
+        e: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+
+        stake_internal(&e, &user, amount, true)?;
+        enforce_invariants(&e)
+    }
+
+    fn unstake_request(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+        check_unstake_allowed(&e)?;
+
+        unstake_request_internal(&e, &user, amount, false)?;
+        enforce_invariants(&e)
     }
 
     fn claim_slot(e: Env, user: Address, slot: u32) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
         check_not_paused(&e)?;
 
         user.require_auth();
         check_unstake_allowed(&e)?;
 
-        let amount = read_pending_unstake_amount(&e, &user, slot);
+        let pending_unstake_amount = read_pending_unstake_amount(&e, &user, slot);
+        if pending_unstake_amount <= 0 {
+            return Err(PumpBTCStakingError::NoPendingUnstake);
+        }
+
+        let amount = apply_claim_ratio(
+            &e,
+            pending_unstake_amount,
+            read_pending_unstake_ratio(&e, &user, slot),
+        )?;
         let normal_unstake_fee = read_normal_unstake_fee(&e);
         let fee = safe_div(safe_mul(amount, normal_unstake_fee)?, 10000)?;
 
         let block_timestamp = e.ledger().timestamp();
         let pending_unstake_time = read_pending_unstake_time(&e, &user, slot);
 
-        if amount <= 0 {
-            return Err(PumpBTCStakingError::NoPendingUnstake);
-        }
-
         if safe_sub(block_timestamp as i128, pending_unstake_time as i128)?
-            >= safe_mul(safe_sub(MAX_DATE_SLOT as i128, 1)?, SECONDS_PER_DAY as i128)? as i128
+            >= safe_mul(safe_sub(MAX_DATE_SLOT as i128, 1)?, SECONDS_PER_DAY as i128)?
         {
             write_pending_unstake_amount(&e, &user, slot, 0);
+            record_daily_claim(&e, pending_unstake_time, amount)?;
 
-            let total_claimable_amount = read_total_claimable_amount(&e);
-            write_total_claimable_amount(&e, safe_sub(total_claimable_amount, amount)?);
+            record_claim(&e, amount, fee)?;
 
-            let total_requested_amount = read_total_requested_amount(&e);
-            write_total_requested_amount(&e, safe_sub(total_requested_amount, amount)?);
+            let asset_amount = adjust_amount(&e, safe_sub(amount, fee)?, Rounding::Down)?;
+            transfer_asset(&e, &e.current_contract_address(), &user, asset_amount)?;
 
-            let collected_fee = read_collected_fee(&e);
-            write_collected_fee(&e, safe_add(collected_fee, fee)?);
+            event::claim_slot(&e, user, slot, amount, fee, asset_amount);
+            enforce_invariants(&e)
+        } else {
+            Err(PumpBTCStakingError::NotReachedClaimableTime)
+        }
+    }
 
-            let asset_token = read_asset_token_address(&e);
-            let asset_client = token::Client::new(&e, &asset_token);
+    fn claim_all(e: Env, user: Address) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
 
-            asset_client.transfer(
-                &e.current_contract_address(),
-                &user,
-                &adjust_amount(&e, safe_sub(amount, fee)?)?,
-            );
+        user.require_auth();
+        check_unstake_allowed(&e)?;
 
-            event::claim_slot(&e, user, amount, slot);
-            Ok(())
-        } else {
+        let (total_amount, pending_count) = settle_matured_unstakes(&e, &user)?;
+
+        let fee = safe_div(safe_mul(total_amount, read_normal_unstake_fee(&e))?, 10000)?;
+
+        if pending_count == 0 {
+            return Err(PumpBTCStakingError::NoPendingUnstake);
+        }
+        if total_amount <= 0 {
             return Err(PumpBTCStakingError::NotReachedClaimableTime);
         }
+
+        record_claim(&e, total_amount, fee)?;
+
+        let asset_amount = adjust_amount(&e, safe_sub(total_amount, fee)?, Rounding::Down)?;
+        transfer_asset(&e, &e.current_contract_address(), &user, asset_amount)?;
+
+        event::claim_all(&e, user, total_amount, fee, asset_amount);
+        enforce_invariants(&e)
     }
 
-    fn claim_all(e: Env, user: Address) -> Result<(), PumpBTCStakingError> {
+    // Pays out the rewards accrued on the user's pumpBTC, 0 when there are none
+    fn claim_rewards(e: Env, user: Address) -> Result<i128, PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+
+        let amount = claim_pump_rewards(&e, &user)?;
+        if amount <= 0 {
+            return Ok(0);
+        }
+        write_reward_reserve(&e, safe_sub(read_reward_reserve(&e), amount)?);
+
+        let asset_amount = adjust_amount(&e, amount, Rounding::Down)?;
+        transfer_asset(&e, &e.current_contract_address(), &user, asset_amount)?;
+
+        event::claim_rewards(&e, user, amount, asset_amount);
+        enforce_invariants(&e)?;
+        Ok(amount)
+    }
+
+    // Stakes `amount` and locks the pumpBTC minted for it for the tier's term,
+    // boosting its rewards and points by the tier multiplier. Returns the id
+    // of the new lock position.
+    fn stake_locked(
+        e: Env,
+        user: Address,
+        amount: i128,
+        tier: LockTier,
+    ) -> Result<u32, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+
+        let pump_amount = stake_internal(&e, &user, amount, false)?;
+        let boost = safe_div(
+            safe_mul(pump_amount, safe_sub(tier.multiplier(), 10000)?)?,
+            10000,
+        )?;
+        lock_pump(&e, &user, pump_amount, boost)?;
+
+        let id = read_next_lock_id(&e, &user);
+        write_next_lock_id(&e, &user, id + 1);
+
+        let start_time = e.ledger().timestamp();
+        let position = LockPosition {
+            id,
+            tier,
+            amount: pump_amount,
+            boost,
+            start_time,
+            unlock_time: start_time + tier.duration(),
+        };
+        write_lock_position(&e, &user, &position);
+
+        event::stake_locked(&e, user, position);
+        enforce_invariants(&e)?;
+        Ok(id)
+    }
+
+    // Releases a lock position and returns the pumpBTC freed. Before its
+    // unlock time the lock penalty is burned from the position. Its BTC value
+    // moves from the pending stake to the collected fees. Any part the
+    // operator has already withdrawn is owed by them until deposited with
+    // `deposit_lock_penalty`.
+    fn unlock(e: Env, user: Address, lock_id: u32) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+
+        let position =
+            read_lock_position(&e, &user, lock_id).ok_or(PumpBTCStakingError::LockNotFound)?;
+        remove_lock_position(&e, &user, lock_id);
+
+        unlock_pump(&e, &user, position.amount, position.boost)?;
+
+        let mut penalty = 0;
+        let mut penalty_value = 0;
+        if e.ledger().timestamp() < position.unlock_time {
+            penalty = safe_div(safe_mul(position.amount, read_lock_penalty(&e))?, 10000)?;
+            penalty_value = pump_to_btc(&e, penalty, Rounding::Down)?;
+        }
+        if penalty > 0 {
+            let total_staking_amount = read_total_staking_amount(&e);
+            write_total_staking_amount(&e, safe_sub(total_staking_amount, penalty)?);
+
+            let pending_stake_amount = read_pending_stake_amount(&e);
+            let covered_value = penalty_value.min(pending_stake_amount);
+            write_pending_stake_amount(&e, safe_sub(pending_stake_amount, covered_value)?);
+            accrue_fee(&e, FeeBucket::LockPenalty, covered_value)?;
+
+            let owed_value = safe_sub(penalty_value, covered_value)?;
+            write_owed_lock_penalty(&e, safe_add(read_owed_lock_penalty(&e), owed_value)?);
+
+            burn_pump(&e, &user, penalty, false)?;
+        }
+
+        let amount = safe_sub(position.amount, penalty)?;
+        event::unlock(&e, user, lock_id, amount, penalty, penalty_value);
+        enforce_invariants(&e)?;
+        Ok(amount)
+    }
+
+    fn unstake_instant(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
         check_not_paused(&e)?;
 
         user.require_auth();
         check_unstake_allowed(&e)?;
 
-        let mut total_amount: i128 = 0;
-        let mut pending_count: u32 = 0;
-        let block_timestamp = e.ledger().timestamp();
+        unstake_instant_internal(&e, &user, amount, None)?;
+        enforce_invariants(&e)
+    }
 
-        for slot in 0..MAX_DATE_SLOT {
-            let amount = read_pending_unstake_amount(&e, &user, slot);
-            let pending_unstake_time = read_pending_unstake_time(&e, &user, slot);
-            let ready_to_claim = safe_sub(block_timestamp as i128, pending_unstake_time as i128)?
-                >= safe_mul(safe_sub(MAX_DATE_SLOT as i128, 1)?, SECONDS_PER_DAY as i128)? as i128;
-
-            if amount > 0 {
-                pending_count = safe_add(pending_count as i128, 1)? as u32;
-                if ready_to_claim {
-                    total_amount = safe_add(total_amount, amount)?;
-                    write_pending_unstake_amount(&e, &user, slot, 0);
-                }
+    fn stake_native(
+        e: Env,
+        user: Address,
+        asset_amount: i128,
+    ) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+
+        let amount = to_pump_amount_exact(&e, asset_amount)?;
+        let pump_amount = stake_internal(&e, &user, amount, false)?;
+        enforce_invariants(&e)?;
+        Ok(pump_amount)
+    }
+
+    fn unstake_instant_native(
+        e: Env,
+        user: Address,
+        asset_amount: i128,
+    ) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        user.require_auth();
+        check_unstake_allowed(&e)?;
+
+        // Burn enough pumpBTC to be worth at least the requested amount
+        let value = to_pump_amount_exact(&e, asset_amount)?;
+        let pump_amount =
+            round_to_pump_unit(&e, btc_to_pump(&e, value, Rounding::Up)?, Rounding::Up)?;
+        unstake_instant_internal(&e, &user, pump_amount, None)?;
+        enforce_invariants(&e)?;
+        Ok(pump_amount)
+    }
+
+    fn set_keeper_tip(
+        e: Env,
+        user: Address,
+        opted_in: bool,
+        keeper_tip: i128,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+
+        user.require_auth();
+
+        if opted_in {
+            if !(0..10000).contains(&keeper_tip) {
+                return Err(PumpBTCStakingError::FeeShouldBeBetween0And10000);
             }
+            write_keeper_tip(&e, &user, keeper_tip);
+        } else {
+            remove_keeper_tip(&e, &user);
         }
 
-        let fee = safe_div(safe_mul(total_amount, read_normal_unstake_fee(&e))?, 10000)?;
+        event::set_keeper_tip(&e, user, opted_in, keeper_tip);
+        Ok(())
+    }
+
+    fn claim_for(e: Env, keeper: Address, users: Vec<Address>) -> Result<u32, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
+        check_not_paused(&e)?;
+
+        keeper.require_auth();
+        check_unstake_allowed(&e)?;
+
+        let normal_unstake_fee = read_normal_unstake_fee(&e);
+
+        let mut claimed_count: u32 = 0;
+        let mut total_keeper_tip: i128 = 0;
+
+        for user in users.iter() {
+            // Users that did not opt in are reported as skipped
+            let keeper_tip_rate = match read_keeper_tip(&e, &user) {
+                Some(keeper_tip_rate) => keeper_tip_rate,
+                None => {
+                    event::claim_for(&e, keeper.clone(), user, false, 0, 0, 0);
+                    continue;
+                }
+            };
+
+            // Users whose matured claims are not funded yet are skipped too,
+            // reported with the amount that could not be paid
+            let matured_amount = matured_unstake_amount(&e, &user)?;
+            if matured_amount <= 0 || matured_amount > read_total_claimable_amount(&e) {
+                event::claim_for(&e, keeper.clone(), user, false, matured_amount, 0, 0);
+                continue;
+            }
+
+            let (total_amount, _) = settle_matured_unstakes(&e, &user)?;
+
+            let fee = safe_div(safe_mul(total_amount, normal_unstake_fee)?, 10000)?;
+            record_claim(&e, total_amount, fee)?;
+
+            let payout = safe_sub(total_amount, fee)?;
+            let keeper_tip = safe_div(safe_mul(payout, keeper_tip_rate)?, 10000)?;
+            total_keeper_tip = safe_add(total_keeper_tip, keeper_tip)?;
+
+            transfer_asset(
+                &e,
+                &e.current_contract_address(),
+                &user,
+                adjust_amount(&e, safe_sub(payout, keeper_tip)?, Rounding::Down)?,
+            )?;
 
-        if pending_count <= 0 {
-            return Err(PumpBTCStakingError::NoPendingUnstake);
-        }
-        if total_amount <= 0 {
-            return Err(PumpBTCStakingError::NotReachedClaimableTime);
+            claimed_count += 1;
+            event::claim_for(
+                &e,
+                keeper.clone(),
+                user,
+                true,
+                total_amount,
+                fee,
+                keeper_tip,
+            );
         }
 
-        write_total_claimable_amount(&e, safe_sub(read_total_claimable_amount(&e), total_amount)?);
-        write_total_requested_amount(&e, safe_sub(read_total_requested_amount(&e), total_amount)?);
+        if total_keeper_tip > 0 {
+            transfer_asset(
+                &e,
+                &e.current_contract_address(),
+                &keeper,
+                adjust_amount(&e, total_keeper_tip, Rounding::Down)?,
+            )?;
+        }
 
-        write_collected_fee(&e, safe_add(read_collected_fee(&e), fee)?);
+        enforce_invariants(&e)?;
+        Ok(claimed_count)
+    }
 
-        let asset_token = read_asset_token_address(&e);
-        let asset_client = token::Client::new(&e, &asset_token);
+    fn set_intent_signer(
+        e: Env,
+        user: Address,
+        public_key: BytesN<32>,
+    ) -> Result<(), PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        check_schema_version(&e)?;
 
-        asset_client.transfer(
-            &e.current_contract_address(),
-            &user,
-            &adjust_amount(&e, safe_sub(total_amount, fee)?)?,
-        );
+        user.require_auth();
 
-        event::claim_all(&e, user, total_amount);
+        write_intent_signer(&e, &user, &public_key);
+        event::set_intent_signer(&e, user, public_key);
         Ok(())
     }
 
-    fn unstake_instant(e: Env, user: Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+    fn submit_intent(
+        e: Env,
+        relayer: Address,
+        intent: StakingIntent,
+        signature: BytesN<64>,
+    ) -> Result<(), PumpBTCStakingError> {
         extend_instance_ttl(&e);
+        check_schema_version(&e)?;
         check_not_paused(&e)?;
 
-        user.require_auth();
-        check_unstake_allowed(&e)?;
+        // The user's signature replaces `user.require_auth()`
+        relayer.require_auth();
+        verify_intent(&e, &relayer, &intent, &signature)?;
 
-        let fee = safe_div(safe_mul(amount, read_instant_unstake_fee(&e))?, 10000)?;
+        let user = &intent.user;
+        let relayer_fee = intent.relayer_fee;
 
-        check_nonnegative_amount(amount)?;
+        match intent.action {
+            IntentAction::Stake => {
+                // The user only signed the intent, so funds come from an allowance
+                stake_internal(&e, user, safe_sub(intent.amount, relayer_fee)?, true)?;
 
-        let pending_stake_amount = read_pending_stake_amount(&e);
-        if amount > pending_stake_amount {
-            return Err(PumpBTCStakingError::InsufficientPendingStakeAmount);
+                // The relayer fee is the part of the signed amount not staked
+                if relayer_fee > 0 {
+                    transfer_asset_from(&e, user, &relayer, adjust_amount_exact(&e, relayer_fee)?)?;
+                }
+            }
+            IntentAction::UnstakeRequest => {
+                check_unstake_allowed(&e)?;
+
+                // No asset moves until claim, so the relayer fee is paid by
+                // transferring pumpBTC worth at least the fee out of the
+                // signed amount
+                let pump_fee = round_to_pump_unit(
+                    &e,
+                    btc_to_pump(&e, relayer_fee, Rounding::Up)?,
+                    Rounding::Up,
+                )?;
+                if pump_fee > 0 {
+                    transfer_pump_from(&e, user, &relayer, pump_fee)?;
+                }
+                unstake_request_internal(&e, user, safe_sub(intent.amount, pump_fee)?, true)?;
+            }
+            IntentAction::UnstakeInstant => {
+                check_unstake_allowed(&e)?;
+                unstake_instant_internal(
+                    &e,
+                    user,
+                    intent.amount,
+                    Some((relayer.clone(), relayer_fee)),
+                )?;
+            }
         }
 
-        let total_staking_amount = read_total_staking_amount(&e);
-        write_total_staking_amount(&e, safe_sub(total_staking_amount, amount)?);
-
-        write_pending_stake_amount(&e, safe_sub(pending_stake_amount, amount)?);
-
-        let collected_fee = read_collected_fee(&e);
-        write_collected_fee(&e, safe_add(collected_fee, fee)?);
-
-        // Burn user's pumpBTC
-        let pump_token = read_pump_token_address(&e);
-        e.invoke_contract::<()>(
-            &pump_token,
-            &Symbol::new(&e, "burn"),
-            (user.clone(), amount).into_val(&e),
-        );
-
-        let asset_token = read_asset_token_address(&e);
-        let asset_client = token::Client::new(&e, &asset_token);
-
-        asset_client.transfer(
-            &e.current_contract_address(),
-            &user,
-            &adjust_amount(&e, safe_sub(amount, fee)?)?,
-        );
-
-        event::unstake_instant(&e, user, amount);
-        Ok(())
+        event::submit_intent(&e, relayer, intent);
+        enforce_invariants(&e)
     }
 
     // ========================= Getter Functions =========================
@@ -654,6 +1588,16 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         read_pending_administrator(&e)
     }
 
+    fn version(e: Env) -> u32 {
+        extend_instance_ttl(&e);
+        read_schema_version(&e)
+    }
+
+    fn get_pending_upgrade(e: Env) -> Option<PendingUpgrade> {
+        extend_instance_ttl(&e);
+        read_pending_upgrade(&e)
+    }
+
     fn get_max_date_slot(e: Env) -> u32 {
         extend_instance_ttl(&e);
         MAX_DATE_SLOT
@@ -664,6 +1608,47 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         read_pump_token_address(&e)
     }
 
+    fn get_pump_token_kind(e: Env) -> PumpTokenKind {
+        extend_instance_ttl(&e);
+        read_pump_token_kind(&e)
+    }
+
+    fn get_exchange_rate(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_exchange_rate(&e)
+    }
+
+    fn get_claim_ratio(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_claim_ratio(&e)
+    }
+
+    fn get_exchange_rate_bounds(e: Env) -> ExchangeRateBounds {
+        extend_instance_ttl(&e);
+        read_exchange_rate_bounds(&e)
+    }
+
+    fn get_rate_oracle(e: Env) -> Option<Address> {
+        extend_instance_ttl(&e);
+        read_rate_oracle(&e)
+    }
+
+    // Most recent rate updates, newest first
+    fn get_exchange_rate_history(e: Env, limit: u32) -> Vec<ExchangeRateUpdate> {
+        extend_instance_ttl(&e);
+
+        let count = read_exchange_rate_history_count(&e);
+        let first = count.saturating_sub(limit.min(MAX_RATE_HISTORY));
+
+        let mut history = Vec::new(&e);
+        for index in (first..count).rev() {
+            if let Some(update) = read_exchange_rate_update(&e, index) {
+                history.push_back(update);
+            }
+        }
+        history
+    }
+
     fn get_asset_token(e: Env) -> Address {
         extend_instance_ttl(&e);
         read_asset_token_address(&e)
@@ -699,16 +1684,118 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         read_pending_stake_amount(&e)
     }
 
+    fn get_liquidity_buffer(e: Env) -> (i128, i128) {
+        extend_instance_ttl(&e);
+        (
+            read_liquidity_buffer_amount(&e),
+            read_liquidity_buffer_bps(&e),
+        )
+    }
+
+    fn get_reserved_liquidity(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        reserved_liquidity(&e)
+    }
+
+    fn get_withdrawable_amount(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        safe_sub(read_pending_stake_amount(&e), reserved_liquidity(&e)?)
+    }
+
     fn get_collected_fee(e: Env) -> i128 {
         extend_instance_ttl(&e);
         read_collected_fee(&e)
     }
 
+    fn get_unallocated_amount(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_unallocated_amount(&e)
+    }
+
+    fn get_reward_reserve(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_reward_reserve(&e)
+    }
+
+    // Rewards `user` could claim now, 0 for a Stellar Asset Contract pumpBTC
+    fn get_accrued_rewards(e: Env, user: Address) -> i128 {
+        extend_instance_ttl(&e);
+        pump_accrued_rewards(&e, &user)
+    }
+
+    fn check_solvency(e: Env) -> Result<SolvencyReport, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        solvency_report(&e)
+    }
+
+    fn get_funding_schedule(
+        e: Env,
+        days: u32,
+    ) -> Result<Vec<DailyObligation>, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        funding_schedule(&e, days)
+    }
+
+    fn get_funding_shortfall(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        funding_shortfall(&e)
+    }
+
+    fn get_collected_normal_fee(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_bucket_collected_fee(&e, FeeBucket::NormalUnstake)
+    }
+
+    fn get_collected_instant_fee(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_bucket_collected_fee(&e, FeeBucket::InstantUnstake)
+    }
+
+    fn get_lifetime_normal_fee(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_bucket_lifetime_fee(&e, FeeBucket::NormalUnstake)
+    }
+
+    fn get_lifetime_instant_fee(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_bucket_lifetime_fee(&e, FeeBucket::InstantUnstake)
+    }
+
+    fn get_collected_lock_penalty(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_bucket_collected_fee(&e, FeeBucket::LockPenalty)
+    }
+
+    fn get_lifetime_lock_penalty(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_bucket_lifetime_fee(&e, FeeBucket::LockPenalty)
+    }
+
     fn get_operator(e: Env) -> Option<Address> {
         extend_instance_ttl(&e);
         read_operator(&e)
     }
 
+    fn is_withdraw_destination(e: Env, destination: Address) -> bool {
+        extend_instance_ttl(&e);
+        is_withdraw_destination(&e, &destination)
+    }
+
+    fn get_withdraw_limit(e: Env) -> (i128, u64) {
+        extend_instance_ttl(&e);
+        (read_withdraw_limit(&e), read_withdraw_window(&e))
+    }
+
+    fn get_remaining_withdraw_allowance(e: Env) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+        remaining_withdraw_allowance(&e)
+    }
+
+    fn is_operator_paused(e: Env) -> bool {
+        extend_instance_ttl(&e);
+        read_operator_paused(&e)
+    }
+
     fn get_normal_unstake_fee(e: Env) -> i128 {
         extend_instance_ttl(&e);
         read_normal_unstake_fee(&e)
@@ -719,11 +1806,74 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
         read_instant_unstake_fee(&e)
     }
 
+    fn get_instant_fee_curve(e: Env) -> Option<InstantFeeCurve> {
+        extend_instance_ttl(&e);
+        read_instant_fee_curve(&e)
+    }
+
+    // Net BTC amount (8 decimals) that `unstake_instant` would pay out now for
+    // `amount` pumpBTC
+    fn preview_unstake_instant(e: Env, amount: i128) -> Result<i128, PumpBTCStakingError> {
+        extend_instance_ttl(&e);
+
+        check_nonnegative_amount(amount)?;
+        let value = pump_to_btc(&e, amount, Rounding::Down)?;
+        if value > read_pending_stake_amount(&e) {
+            return Err(PumpBTCStakingError::InsufficientPendingStakeAmount);
+        }
+
+        safe_sub(value, instant_unstake_fee(&e, value)?)
+    }
+
     fn get_only_allow_stake(e: Env) -> bool {
         extend_instance_ttl(&e);
         read_only_allow_stake(&e)
     }
 
+    fn get_lock_penalty(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_lock_penalty(&e)
+    }
+
+    fn get_owed_lock_penalty(e: Env) -> i128 {
+        extend_instance_ttl(&e);
+        read_owed_lock_penalty(&e)
+    }
+
+    fn get_lock_position(e: Env, user: Address, lock_id: u32) -> Option<LockPosition> {
+        extend_instance_ttl(&e);
+        read_lock_position(&e, &user, lock_id)
+    }
+
+    // Walks every lock id the user has been given, skipping released ones.
+    // Users with many past locks can page through `get_lock_position` up to
+    // `get_next_lock_id` instead.
+    fn get_lock_positions(e: Env, user: Address) -> Vec<LockPosition> {
+        extend_instance_ttl(&e);
+        let mut positions = Vec::new(&e);
+        for lock_id in 0..read_next_lock_id(&e, &user) {
+            if let Some(position) = read_lock_position(&e, &user, lock_id) {
+                positions.push_back(position);
+            }
+        }
+        positions
+    }
+
+    fn get_next_lock_id(e: Env, user: Address) -> u32 {
+        extend_instance_ttl(&e);
+        read_next_lock_id(&e, &user)
+    }
+
+    fn get_treasury(e: Env) -> Option<Address> {
+        extend_instance_ttl(&e);
+        read_treasury(&e)
+    }
+
+    fn get_fee_splits(e: Env) -> Vec<FeeSplit> {
+        extend_instance_ttl(&e);
+        read_fee_splits(&e)
+    }
+
     fn get_pending_unstake_time(e: Env, user: Address, slot: u32) -> u64 {
         extend_instance_ttl(&e);
         read_pending_unstake_time(&e, &user, slot)
@@ -731,11 +1881,380 @@ impl PumpBTCStakingContractTrait for PumpBTCStaking {
 
     fn get_pending_unstake_amount(e: Env, user: Address, slot: u32) -> i128 {
         extend_instance_ttl(&e);
-        read_pending_unstake_amount(&e, &user, slot)
+        let requested_amount = read_pending_unstake_amount(&e, &user, slot);
+        // The claim ratio only falls, so the requested amount bounds the
+        // claim should the scaling overflow
+        apply_claim_ratio(
+            &e,
+            requested_amount,
+            read_pending_unstake_ratio(&e, &user, slot),
+        )
+        .unwrap_or(requested_amount)
+    }
+
+    fn get_keeper_tip(e: Env, user: Address) -> Option<i128> {
+        extend_instance_ttl(&e);
+        read_keeper_tip(&e, &user)
+    }
+
+    fn get_intent_signer(e: Env, user: Address) -> Option<BytesN<32>> {
+        extend_instance_ttl(&e);
+        read_intent_signer(&e, &user)
+    }
+
+    fn get_intent_nonce(e: Env, user: Address) -> u64 {
+        extend_instance_ttl(&e);
+        read_intent_nonce(&e, &user)
     }
 
     fn is_paused(e: Env) -> bool {
         extend_instance_ttl(&e);
         read_paused(&e)
     }
+
+    fn get_invariant_violation(e: Env) -> Option<Invariant> {
+        extend_instance_ttl(&e);
+        read_invariant_violation(&e)
+    }
+}
+
+// Clears every matured pending unstake of `user` and returns the matured total,
+// after any reported losses, together with the number of slots that still held
+// a pending request.
+fn settle_matured_unstakes(e: &Env, user: &Address) -> Result<(i128, u32), PumpBTCStakingError> {
+    let mut total_amount: i128 = 0;
+    let mut pending_count: u32 = 0;
+    let block_timestamp = e.ledger().timestamp();
+
+    for slot in 0..MAX_DATE_SLOT {
+        let pending_unstake_amount = read_pending_unstake_amount(e, user, slot);
+        let pending_unstake_time = read_pending_unstake_time(e, user, slot);
+        let ready_to_claim = safe_sub(block_timestamp as i128, pending_unstake_time as i128)?
+            >= safe_mul(safe_sub(MAX_DATE_SLOT as i128, 1)?, SECONDS_PER_DAY as i128)?;
+
+        if pending_unstake_amount > 0 {
+            pending_count = safe_add(pending_count as i128, 1)? as u32;
+            if ready_to_claim {
+                let amount = apply_claim_ratio(
+                    e,
+                    pending_unstake_amount,
+                    read_pending_unstake_ratio(e, user, slot),
+                )?;
+                total_amount = safe_add(total_amount, amount)?;
+                write_pending_unstake_amount(e, user, slot, 0);
+                record_daily_claim(e, pending_unstake_time, amount)?;
+            }
+        }
+    }
+
+    Ok((total_amount, pending_count))
+}
+
+// Sums the matured pending unstakes of `user`, after any reported losses,
+// without clearing them.
+fn matured_unstake_amount(e: &Env, user: &Address) -> Result<i128, PumpBTCStakingError> {
+    let mut total_amount: i128 = 0;
+    let block_timestamp = e.ledger().timestamp();
+
+    for slot in 0..MAX_DATE_SLOT {
+        let pending_unstake_amount = read_pending_unstake_amount(e, user, slot);
+        let pending_unstake_time = read_pending_unstake_time(e, user, slot);
+        let ready_to_claim = safe_sub(block_timestamp as i128, pending_unstake_time as i128)?
+            >= safe_mul(safe_sub(MAX_DATE_SLOT as i128, 1)?, SECONDS_PER_DAY as i128)?;
+
+        if pending_unstake_amount > 0 && ready_to_claim {
+            let amount = apply_claim_ratio(
+                e,
+                pending_unstake_amount,
+                read_pending_unstake_ratio(e, user, slot),
+            )?;
+            total_amount = safe_add(total_amount, amount)?;
+        }
+    }
+
+    Ok(total_amount)
+}
+
+// Moves a settled claim out of the global totals and books its fee. Claims
+// are only paid out of operator deposits, never out of the pending stake.
+fn record_claim(e: &Env, amount: i128, fee: i128) -> Result<(), PumpBTCStakingError> {
+    let total_claimable_amount = read_total_claimable_amount(e);
+    if amount > total_claimable_amount {
+        return Err(PumpBTCStakingError::ClaimNotFunded);
+    }
+    write_total_claimable_amount(e, safe_sub(total_claimable_amount, amount)?);
+    write_total_requested_amount(e, safe_sub(read_total_requested_amount(e), amount)?);
+
+    accrue_fee(e, FeeBucket::NormalUnstake, fee)
+}
+
+// Books a claimed slot against the day it matured on.
+fn record_daily_claim(
+    e: &Env,
+    pending_unstake_time: u64,
+    amount: i128,
+) -> Result<(), PumpBTCStakingError> {
+    let maturity_day = get_maturity_day(pending_unstake_time);
+    let (daily_requested_amount, daily_claimed_amount) = daily_amounts(e, maturity_day)?;
+    write_daily_amounts(
+        e,
+        maturity_day,
+        daily_requested_amount,
+        safe_add(daily_claimed_amount, amount)?,
+    );
+    Ok(())
+}
+
+// Stores a day's amounts at the current claim ratio
+fn write_daily_amounts(e: &Env, day: u64, requested_amount: i128, claimed_amount: i128) {
+    write_daily_requested_amount(e, day, requested_amount);
+    write_daily_claimed_amount(e, day, claimed_amount);
+    write_daily_claim_ratio(e, day, read_claim_ratio(e));
+}
+
+// A Stellar Asset Contract pumpBTC must have this contract as its admin and
+// may use fewer than 8 decimals, see `pump_token_amount_exact`
+fn initialize_internal(
+    e: &Env,
+    admin: &Address,
+    pump_token_address: &Address,
+    asset_token_address: &Address,
+    pump_token_kind: PumpTokenKind,
+) -> Result<(), PumpBTCStakingError> {
+    if has_administrator(e) {
+        return Err(PumpBTCStakingError::AlreadyInitialized);
+    }
+    write_administrator(e, admin);
+
+    write_pump_token_address(e, pump_token_address);
+    write_asset_token_address(e, asset_token_address);
+
+    let asset_client = token::Client::new(e, asset_token_address);
+    // Assets with any number of decimals are converted both ways,
+    // see `adjust_amount` and `to_pump_amount`
+    write_asset_decimal(e, asset_client.decimals());
+
+    let pump_token_client = token::Client::new(e, pump_token_address);
+    let pump_token_decimal = pump_token_client.decimals();
+    match pump_token_kind {
+        PumpTokenKind::Minter => {
+            if pump_token_decimal != 8 {
+                return Err(PumpBTCStakingError::InvalidPumpTokenDecimal);
+            }
+        }
+        PumpTokenKind::StellarAsset => {
+            if pump_token_decimal > 8 {
+                return Err(PumpBTCStakingError::InvalidPumpTokenDecimal);
+            }
+            let sac_admin = token::StellarAssetClient::new(e, pump_token_address).try_admin();
+            if sac_admin != Ok(Ok(e.current_contract_address())) {
+                return Err(PumpBTCStakingError::InvalidPumpTokenAdmin);
+            }
+        }
+    }
+    write_pump_token_kind(e, pump_token_kind);
+    write_pump_token_decimal(e, pump_token_decimal);
+
+    write_normal_unstake_fee(e, 0);
+    write_instant_unstake_fee(e, 300);
+    write_only_allow_stake(e, true);
+    write_schema_version(e, SCHEMA_VERSION);
+
+    extend_instance_ttl(e);
+    Ok(())
+}
+
+// Books `fee` into the uncollected total and the bucket's own counters.
+fn accrue_fee(e: &Env, bucket: FeeBucket, fee: i128) -> Result<(), PumpBTCStakingError> {
+    if fee <= 0 {
+        return Ok(());
+    }
+
+    write_collected_fee(e, safe_add(read_collected_fee(e), fee)?);
+    write_bucket_collected_fee(
+        e,
+        bucket,
+        safe_add(read_bucket_collected_fee(e, bucket), fee)?,
+    );
+    write_bucket_lifetime_fee(
+        e,
+        bucket,
+        safe_add(read_bucket_lifetime_fee(e, bucket), fee)?,
+    );
+
+    event::accrue_fee(e, bucket, fee);
+    Ok(())
+}
+
+// Funds are pulled with a direct transfer under the user's authorization, or
+// through an allowance granted to this contract when `from_allowance` is set.
+// `amount` is in BTC; the pumpBTC minted for it at the current exchange rate
+// is returned.
+// Pulls `amount` BTC from the operator into the contract. Shared by
+// `deposit` and `deposit_rewards`, which only differ in what they book the
+// funds against. Returns the operator and the asset amount moved.
+fn deposit_internal(e: &Env, amount: i128) -> Result<(Address, i128), PumpBTCStakingError> {
+    let operator = read_operator(e);
+    if operator.is_none() {
+        return Err(PumpBTCStakingError::NoOperatorSet);
+    }
+    let operator = operator.unwrap();
+    operator.require_auth();
+
+    check_nonnegative_amount(amount)?;
+
+    let asset_amount = adjust_amount_exact(e, amount)?;
+    transfer_asset(e, &operator, &e.current_contract_address(), asset_amount)?;
+    Ok((operator, asset_amount))
+}
+
+fn stake_internal(
+    e: &Env,
+    user: &Address,
+    amount: i128,
+    from_allowance: bool,
+) -> Result<i128, PumpBTCStakingError> {
+    check_nonnegative_amount(amount)?;
+
+    let total_staking_amount = read_total_staking_amount(e);
+    let total_staking_cap = read_total_staking_cap(e);
+
+    let total_staking_value = pump_to_btc(e, total_staking_amount, Rounding::Up)?;
+    if safe_add(total_staking_value, amount)? > total_staking_cap {
+        return Err(PumpBTCStakingError::ExceedStakingCap);
+    }
+
+    let pump_amount =
+        round_to_pump_unit(e, btc_to_pump(e, amount, Rounding::Down)?, Rounding::Down)?;
+    check_nonnegative_amount(pump_amount)?;
+
+    write_total_staking_amount(e, safe_add(total_staking_amount, pump_amount)?);
+    let pending_stake_amount = read_pending_stake_amount(e);
+    write_pending_stake_amount(e, safe_add(pending_stake_amount, amount)?);
+
+    let asset_amount = adjust_amount_exact(e, amount)?;
+    if from_allowance {
+        transfer_asset_from(e, user, &e.current_contract_address(), asset_amount)?;
+    } else {
+        transfer_asset(e, user, &e.current_contract_address(), asset_amount)?;
+    }
+
+    // Mint pumpBTC to user
+    mint_pump(e, user, pump_amount)?;
+
+    event::stake(e, user.clone(), amount, pump_amount, asset_amount);
+    Ok(pump_amount)
+}
+
+// `from_allowance` is set when the user did not authorize this call directly,
+// see `burn_pump`
+fn unstake_request_internal(
+    e: &Env,
+    user: &Address,
+    amount: i128,
+    from_allowance: bool,
+) -> Result<(), PumpBTCStakingError> {
+    check_nonnegative_amount(amount)?;
+
+    // The claim is fixed in BTC at the current exchange rate
+    let value = pump_to_btc(e, amount, Rounding::Down)?;
+    check_nonnegative_amount(value)?;
+
+    let block_timestamp = e.ledger().timestamp();
+    let slot = get_date_slot(block_timestamp);
+
+    // Check if the user can request unstake in this slot
+    let pending_unstake_time = read_pending_unstake_time(e, user, slot);
+    let pending_unstake_amount = apply_claim_ratio(
+        e,
+        read_pending_unstake_amount(e, user, slot),
+        read_pending_unstake_ratio(e, user, slot),
+    )?;
+
+    if safe_sub(block_timestamp as i128, pending_unstake_time as i128)? < SECONDS_PER_DAY as i128
+        || pending_unstake_amount == 0
+    {
+        write_pending_unstake_time(e, user, slot, block_timestamp);
+        write_pending_unstake_amount(e, user, slot, safe_add(pending_unstake_amount, value)?);
+        write_pending_unstake_ratio(e, user, slot, read_claim_ratio(e));
+
+        let total_staking_amount = read_total_staking_amount(e);
+        write_total_staking_amount(e, safe_sub(total_staking_amount, amount)?);
+
+        let total_requested_amount = read_total_requested_amount(e);
+        write_total_requested_amount(e, safe_add(total_requested_amount, value)?);
+
+        let maturity_day = get_maturity_day(block_timestamp);
+        let (daily_requested_amount, daily_claimed_amount) = daily_amounts(e, maturity_day)?;
+        write_daily_amounts(
+            e,
+            maturity_day,
+            safe_add(daily_requested_amount, value)?,
+            daily_claimed_amount,
+        );
+
+        // Burn user's pumpBTC
+        burn_pump(e, user, amount, from_allowance)?;
+
+        event::unstake_request(e, user.clone(), value, amount, slot);
+        Ok(())
+    } else {
+        Err(PumpBTCStakingError::ClaimPreviousUnstakeFirst)
+    }
+}
+
+// `amount` is in pumpBTC and paid out at its BTC value at the current exchange
+// rate. `relayer` optionally takes a cut of the user's net payout, in BTC.
+// Relayed unstakes come from a signed intent, so pumpBTC is burned through an
+// allowance rather than the user's authorization.
+fn unstake_instant_internal(
+    e: &Env,
+    user: &Address,
+    amount: i128,
+    relayer: Option<(Address, i128)>,
+) -> Result<(), PumpBTCStakingError> {
+    check_nonnegative_amount(amount)?;
+
+    let value = pump_to_btc(e, amount, Rounding::Down)?;
+    let fee = instant_unstake_fee(e, value)?;
+
+    let pending_stake_amount = read_pending_stake_amount(e);
+    if value > pending_stake_amount {
+        return Err(PumpBTCStakingError::InsufficientPendingStakeAmount);
+    }
+
+    let mut payout = safe_sub(value, fee)?;
+    if let Some((_, relayer_fee)) = &relayer {
+        if *relayer_fee > payout {
+            return Err(PumpBTCStakingError::InvalidRelayerFee);
+        }
+        payout = safe_sub(payout, *relayer_fee)?;
+    }
+
+    let total_staking_amount = read_total_staking_amount(e);
+    write_total_staking_amount(e, safe_sub(total_staking_amount, amount)?);
+
+    write_pending_stake_amount(e, safe_sub(pending_stake_amount, value)?);
+
+    accrue_fee(e, FeeBucket::InstantUnstake, fee)?;
+
+    // Burn user's pumpBTC
+    burn_pump(e, user, amount, relayer.is_some())?;
+
+    let asset_amount = adjust_amount(e, payout, Rounding::Down)?;
+    transfer_asset(e, &e.current_contract_address(), user, asset_amount)?;
+
+    if let Some((relayer, relayer_fee)) = relayer {
+        if relayer_fee > 0 {
+            transfer_asset(
+                e,
+                &e.current_contract_address(),
+                &relayer,
+                adjust_amount_exact(e, relayer_fee)?,
+            )?;
+        }
+    }
+
+    event::unstake_instant(e, user.clone(), value, amount, fee, payout, asset_amount);
+    Ok(())
 }
@@ -10,6 +10,8 @@ pub enum PumpBTCStakingError {
     OnlyAllowStakeAtFirst = 3,
     AlreadyInitialized = 4,
     StakingCapTooSmall = 5,
+    // Any basis point setting above 10000: fees, keeper tips, the instant
+    // liquidity buffer and the lock penalty
     FeeShouldBeBetween0And10000 = 6,
     NoFeeToCollect = 7,
     NoPendingStakeAmount = 8,
@@ -24,4 +26,37 @@ pub enum PumpBTCStakingError {
     NoPendingAdminTransfer = 17,
     ContractIsPaused = 18,
     ContractIsNotPaused = 19,
+    NoIntentSignerSet = 20,
+    InvalidIntentNonce = 21,
+    IntentExpired = 22,
+    // The intent names another contract or relayer
+    InvalidIntentTarget = 23,
+    InvalidRelayerFee = 24,
+    // An admin setting outside its valid range: a fee split table, fee curve,
+    // withdraw window or exchange rate bounds
+    InvalidConfig = 25,
+    LossExceedsBacking = 26,
+    DestinationNotAllowed = 27,
+    OperatorFlowsPaused = 28,
+    OperatorFlowsNotPaused = 29,
+    ClaimNotFunded = 30,
+    InsufficientSurplus = 31,
+    LockNotFound = 32,
+    SchemaVersionMismatch = 33,
+    CallerIsNotAdminOrOperator = 34,
+    NoPendingUpgrade = 35,
+    UpgradeNotReady = 36,
+    AmountNotRepresentable = 37,
+    InsufficientAssetBalance = 38,
+    InsufficientAllowance = 39,
+    AssetTransferFailed = 40,
+    InsufficientPumpBalance = 41,
+    PumpTokenCallFailed = 42,
+    InvalidPumpTokenAdmin = 43,
+    NoOwedLockPenalty = 44,
+    CallerIsNotRateUpdater = 45,
+    InvalidExchangeRate = 46,
+    UnsupportedPumpToken = 47,
+    RateChangeTooLarge = 48,
+    RateUpdateTooSoon = 49,
 }
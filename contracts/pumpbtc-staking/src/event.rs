@@ -1,4 +1,21 @@
-use soroban_sdk::{contracttype, symbol_short, Address, Env};
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, IntoVal, Symbol, Val, Vec};
+
+use crate::intent::{IntentAction, StakingIntent};
+use crate::lock::{LockPosition, LockTier};
+use crate::storage::{
+    read_asset_token_address, read_pending_stake_amount, read_reward_reserve,
+    read_total_claimable_amount, read_total_requested_amount, read_total_staking_amount, FeeBucket,
+    FeeSplit, Invariant, MAX_DATE_SLOT, SECONDS_PER_DAY,
+};
+
+// Bump whenever an event payload changes shape or meaning
+pub(crate) const EVENT_VERSION: u32 = 3;
+
+// Every event is published under ("PumpBTCStaking", name, EVENT_VERSION)
+fn publish(e: &Env, name: Symbol, event: impl IntoVal<Env, Val>) {
+    e.events()
+        .publish(("PumpBTCStaking", name, EVENT_VERSION), event);
+}
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -16,8 +33,7 @@ pub(crate) fn set_stake_asset_cap(
         old_total_staking_cap,
         new_total_staking_cap,
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("set_cap")), event);
+    publish(e, symbol_short!("set_cap"), event);
 }
 
 #[contracttype]
@@ -36,8 +52,7 @@ pub(crate) fn set_normal_unstake_fee(
         old_normal_unstake_fee,
         new_normal_unstake_fee,
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("set_nfee")), event);
+    publish(e, symbol_short!("set_nfee"), event);
 }
 
 #[contracttype]
@@ -56,8 +71,39 @@ pub(crate) fn set_instant_unstake_fee(
         old_instant_unstake_fee,
         new_instant_unstake_fee,
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("set_ifee")), event);
+    publish(e, symbol_short!("set_ifee"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetInstantFeeCurveEvent {
+    pub enabled: bool,
+    pub base_fee: i128,
+    pub max_fee: i128,
+}
+
+pub(crate) fn set_instant_fee_curve(e: &Env, enabled: bool, base_fee: i128, max_fee: i128) {
+    let event: SetInstantFeeCurveEvent = SetInstantFeeCurveEvent {
+        enabled,
+        base_fee,
+        max_fee,
+    };
+    publish(e, symbol_short!("set_curve"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetLiquidityBufferEvent {
+    pub buffer_amount: i128,
+    pub buffer_bps: i128,
+}
+
+pub(crate) fn set_liquidity_buffer(e: &Env, buffer_amount: i128, buffer_bps: i128) {
+    let event: SetLiquidityBufferEvent = SetLiquidityBufferEvent {
+        buffer_amount,
+        buffer_bps,
+    };
+    publish(e, symbol_short!("set_buf"), event);
 }
 
 #[contracttype]
@@ -69,11 +115,10 @@ pub struct SetOperatorEvent {
 
 pub(crate) fn set_operator(e: &Env, old_operator: Option<Address>, new_operator: Address) {
     let event: SetOperatorEvent = SetOperatorEvent {
-        old_operator: old_operator,
-        new_operator: new_operator,
+        old_operator,
+        new_operator,
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("set_op")), event);
+    publish(e, symbol_short!("set_op"), event);
 }
 
 #[contracttype]
@@ -84,21 +129,66 @@ pub struct SetOnlyAllowStakeEvent {
 
 pub(crate) fn set_only_allow_stake(e: &Env, only_allow_stake: bool) {
     let event: SetOnlyAllowStakeEvent = SetOnlyAllowStakeEvent { only_allow_stake };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("set_allow")), event);
+    publish(e, symbol_short!("set_allow"), event);
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct CollectFeeEvent {
-    pub admin: Address,
+pub struct SetTreasuryEvent {
+    pub old_treasury: Option<Address>,
+    pub new_treasury: Address,
+}
+
+pub(crate) fn set_treasury(e: &Env, old_treasury: Option<Address>, new_treasury: Address) {
+    let event: SetTreasuryEvent = SetTreasuryEvent {
+        old_treasury,
+        new_treasury,
+    };
+    publish(e, symbol_short!("set_trsy"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetFeeSplitsEvent {
+    pub fee_splits: Vec<FeeSplit>,
+}
+
+pub(crate) fn set_fee_splits(e: &Env, fee_splits: Vec<FeeSplit>) {
+    let event: SetFeeSplitsEvent = SetFeeSplitsEvent { fee_splits };
+    publish(e, symbol_short!("set_split"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccrueFeeEvent {
+    pub bucket: FeeBucket,
     pub fee_amount: i128,
 }
 
-pub(crate) fn collect_fee(e: &Env, admin: Address, fee_amount: i128) {
-    let event: CollectFeeEvent = CollectFeeEvent { admin, fee_amount };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("collect")), event);
+pub(crate) fn accrue_fee(e: &Env, bucket: FeeBucket, fee_amount: i128) {
+    let event: AccrueFeeEvent = AccrueFeeEvent { bucket, fee_amount };
+    publish(e, symbol_short!("fee"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollectFeeEvent {
+    pub recipient: Address,
+    pub fee_amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+}
+
+pub(crate) fn collect_fee(e: &Env, recipient: Address, fee_amount: i128, asset_amount: i128) {
+    let event: CollectFeeEvent = CollectFeeEvent {
+        recipient,
+        fee_amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+    };
+    publish(e, symbol_short!("collect"), event);
 }
 
 #[contracttype]
@@ -106,12 +196,202 @@ pub(crate) fn collect_fee(e: &Env, admin: Address, fee_amount: i128) {
 pub struct WithdrawEvent {
     pub operator: Address,
     pub amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub pending_stake_amount: i128,
 }
 
-pub(crate) fn withdraw(e: &Env, operator: Address, amount: i128) {
-    let event: WithdrawEvent = WithdrawEvent { operator, amount };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("withdraw")), event);
+pub(crate) fn withdraw(e: &Env, operator: Address, amount: i128, asset_amount: i128) {
+    let event: WithdrawEvent = WithdrawEvent {
+        operator,
+        amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        pending_stake_amount: read_pending_stake_amount(e),
+    };
+    publish(e, symbol_short!("withdraw"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetWithdrawDestinationEvent {
+    pub destination: Address,
+    pub allowed: bool,
+}
+
+pub(crate) fn set_withdraw_destination(e: &Env, destination: Address, allowed: bool) {
+    let event: SetWithdrawDestinationEvent = SetWithdrawDestinationEvent {
+        destination,
+        allowed,
+    };
+    publish(e, symbol_short!("set_dest"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawAmountEvent {
+    pub operator: Address,
+    pub destination: Address,
+    pub amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub pending_stake_amount: i128,
+}
+
+pub(crate) fn withdraw_amount(
+    e: &Env,
+    operator: Address,
+    destination: Address,
+    amount: i128,
+    asset_amount: i128,
+) {
+    let event: WithdrawAmountEvent = WithdrawAmountEvent {
+        operator,
+        destination,
+        amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        pending_stake_amount: read_pending_stake_amount(e),
+    };
+    publish(e, symbol_short!("withdr_to"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetWithdrawLimitEvent {
+    pub withdraw_limit: i128,
+    pub withdraw_window: u64,
+}
+
+pub(crate) fn set_withdraw_limit(e: &Env, withdraw_limit: i128, withdraw_window: u64) {
+    let event: SetWithdrawLimitEvent = SetWithdrawLimitEvent {
+        withdraw_limit,
+        withdraw_window,
+    };
+    publish(e, symbol_short!("set_limit"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetRateOracleEvent {
+    pub old_oracle: Option<Address>,
+    pub new_oracle: Address,
+}
+
+pub(crate) fn set_rate_oracle(e: &Env, old_oracle: Option<Address>, new_oracle: Address) {
+    let event: SetRateOracleEvent = SetRateOracleEvent {
+        old_oracle,
+        new_oracle,
+    };
+    publish(e, symbol_short!("set_oracl"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetRateBoundsEvent {
+    pub max_change_bps: i128,
+    pub min_update_interval: u64,
+}
+
+pub(crate) fn set_rate_bounds(e: &Env, max_change_bps: i128, min_update_interval: u64) {
+    let event: SetRateBoundsEvent = SetRateBoundsEvent {
+        max_change_bps,
+        min_update_interval,
+    };
+    publish(e, symbol_short!("set_rbnds"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpdateExchangeRateEvent {
+    pub updater: Address,
+    pub old_rate: i128,
+    pub new_rate: i128,
+    pub timestamp: u64,
+    pub total_staking_amount: i128,
+}
+
+pub(crate) fn update_exchange_rate(e: &Env, updater: Address, old_rate: i128, new_rate: i128) {
+    let event: UpdateExchangeRateEvent = UpdateExchangeRateEvent {
+        updater,
+        old_rate,
+        new_rate,
+        timestamp: e.ledger().timestamp(),
+        total_staking_amount: read_total_staking_amount(e),
+    };
+    publish(e, symbol_short!("rate"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReportLossEvent {
+    pub reporter: Address,
+    pub amount: i128,
+    pub backing: i128,
+    pub claim_ratio: i128,
+    pub old_rate: i128,
+    pub new_rate: i128,
+    pub timestamp: u64,
+    pub total_requested_amount: i128,
+}
+
+pub(crate) fn report_loss(
+    e: &Env,
+    reporter: Address,
+    amount: i128,
+    backing: i128,
+    claim_ratio: i128,
+    old_rate: i128,
+    new_rate: i128,
+) {
+    let event: ReportLossEvent = ReportLossEvent {
+        reporter,
+        amount,
+        backing,
+        claim_ratio,
+        old_rate,
+        new_rate,
+        timestamp: e.ledger().timestamp(),
+        total_requested_amount: read_total_requested_amount(e),
+    };
+    publish(e, symbol_short!("loss"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawLimitExceededEvent {
+    pub operator: Address,
+    pub amount: i128,
+    pub remaining_allowance: i128,
+}
+
+pub(crate) fn withdraw_limit_exceeded(
+    e: &Env,
+    operator: Address,
+    amount: i128,
+    remaining_allowance: i128,
+) {
+    let event: WithdrawLimitExceededEvent = WithdrawLimitExceededEvent {
+        operator,
+        amount,
+        remaining_allowance,
+    };
+    publish(e, symbol_short!("wd_alert"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorUnpausedEvent {
+    pub admin: Address,
+}
+
+pub(crate) fn operator_unpaused(e: &Env, admin: Address) {
+    let event: OperatorUnpausedEvent = OperatorUnpausedEvent { admin };
+    publish(e, symbol_short!("op_resume"), event);
 }
 
 #[contracttype]
@@ -120,16 +400,164 @@ pub struct DepositEvent {
     pub operator: Address,
     pub pumpbtc_staking: Address,
     pub amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub total_claimable_amount: i128,
 }
 
-pub(crate) fn deposit(e: &Env, operator: Address, pumpbtc_staking: Address, amount: i128) {
+pub(crate) fn deposit(
+    e: &Env,
+    operator: Address,
+    pumpbtc_staking: Address,
+    amount: i128,
+    asset_amount: i128,
+) {
     let event: DepositEvent = DepositEvent {
         operator,
         pumpbtc_staking,
         amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        total_claimable_amount: read_total_claimable_amount(e),
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("deposit")), event);
+    publish(e, symbol_short!("deposit"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositRewardsEvent {
+    pub operator: Address,
+    pub amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub reward_reserve: i128,
+}
+
+pub(crate) fn deposit_rewards(e: &Env, operator: Address, amount: i128, asset_amount: i128) {
+    let event: DepositRewardsEvent = DepositRewardsEvent {
+        operator,
+        amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        reward_reserve: read_reward_reserve(e),
+    };
+    publish(e, symbol_short!("dep_rwd"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositLockPenaltyEvent {
+    pub operator: Address,
+    pub amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+}
+
+pub(crate) fn deposit_lock_penalty(e: &Env, operator: Address, amount: i128, asset_amount: i128) {
+    let event: DepositLockPenaltyEvent = DepositLockPenaltyEvent {
+        operator,
+        amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+    };
+    publish(e, symbol_short!("dep_lpen"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimRewardsEvent {
+    pub user: Address,
+    pub amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub reward_reserve: i128,
+}
+
+pub(crate) fn claim_rewards(e: &Env, user: Address, amount: i128, asset_amount: i128) {
+    let event: ClaimRewardsEvent = ClaimRewardsEvent {
+        user,
+        amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        reward_reserve: read_reward_reserve(e),
+    };
+    publish(e, symbol_short!("claim_rwd"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetLockPenaltyEvent {
+    pub old_lock_penalty: i128,
+    pub new_lock_penalty: i128,
+}
+
+pub(crate) fn set_lock_penalty(e: &Env, old_lock_penalty: i128, new_lock_penalty: i128) {
+    let event: SetLockPenaltyEvent = SetLockPenaltyEvent {
+        old_lock_penalty,
+        new_lock_penalty,
+    };
+    publish(e, symbol_short!("set_lpen"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StakeLockedEvent {
+    pub user: Address,
+    pub lock_id: u32,
+    pub tier: LockTier,
+    pub amount: i128,
+    pub boost: i128,
+    pub unlock_time: u64,
+}
+
+pub(crate) fn stake_locked(e: &Env, user: Address, position: LockPosition) {
+    let event: StakeLockedEvent = StakeLockedEvent {
+        user,
+        lock_id: position.id,
+        tier: position.tier,
+        amount: position.amount,
+        boost: position.boost,
+        unlock_time: position.unlock_time,
+    };
+    publish(e, symbol_short!("stk_lock"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnlockEvent {
+    pub user: Address,
+    pub lock_id: u32,
+    pub amount: i128,
+    pub penalty: i128,
+    pub penalty_value: i128,
+    pub timestamp: u64,
+}
+
+pub(crate) fn unlock(
+    e: &Env,
+    user: Address,
+    lock_id: u32,
+    amount: i128,
+    penalty: i128,
+    penalty_value: i128,
+) {
+    let event: UnlockEvent = UnlockEvent {
+        user,
+        lock_id,
+        amount,
+        penalty,
+        penalty_value,
+        timestamp: e.ledger().timestamp(),
+    };
+    publish(e, symbol_short!("unlock"), event);
 }
 
 #[contracttype]
@@ -137,12 +565,26 @@ pub(crate) fn deposit(e: &Env, operator: Address, pumpbtc_staking: Address, amou
 pub struct StakeEvent {
     pub user: Address,
     pub amount: i128,
+    pub pump_amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub total_staking_amount: i128,
+    pub pending_stake_amount: i128,
 }
 
-pub(crate) fn stake(e: &Env, user: Address, amount: i128) {
-    let event: StakeEvent = StakeEvent { user, amount };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("stake")), event);
+pub(crate) fn stake(e: &Env, user: Address, amount: i128, pump_amount: i128, asset_amount: i128) {
+    let event: StakeEvent = StakeEvent {
+        user,
+        amount,
+        pump_amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        total_staking_amount: read_total_staking_amount(e),
+        pending_stake_amount: read_pending_stake_amount(e),
+    };
+    publish(e, symbol_short!("stake"), event);
 }
 
 #[contracttype]
@@ -150,27 +592,65 @@ pub(crate) fn stake(e: &Env, user: Address, amount: i128) {
 pub struct UnstakeRequestEvent {
     pub user: Address,
     pub amount: i128,
+    pub pump_amount: i128,
     pub slot: u32,
+    pub request_time: u64,
+    pub claimable_time: u64,
+    pub total_staking_amount: i128,
+    pub total_requested_amount: i128,
 }
 
-pub(crate) fn unstake_request(e: &Env, user: Address, amount: i128, slot: u32) {
-    let event: UnstakeRequestEvent = UnstakeRequestEvent { user, amount, slot };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("unstake")), event);
+pub(crate) fn unstake_request(e: &Env, user: Address, amount: i128, pump_amount: i128, slot: u32) {
+    let request_time = e.ledger().timestamp();
+    let event: UnstakeRequestEvent = UnstakeRequestEvent {
+        user,
+        amount,
+        pump_amount,
+        slot,
+        request_time,
+        claimable_time: request_time + (MAX_DATE_SLOT as u64 - 1) * SECONDS_PER_DAY,
+        total_staking_amount: read_total_staking_amount(e),
+        total_requested_amount: read_total_requested_amount(e),
+    };
+    publish(e, symbol_short!("unstake"), event);
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ClaimSlotEvent {
     pub user: Address,
-    pub amount: i128,
     pub slot: u32,
+    pub amount: i128,
+    pub fee: i128,
+    pub net_amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub total_requested_amount: i128,
+    pub total_claimable_amount: i128,
 }
 
-pub(crate) fn claim_slot(e: &Env, user: Address, amount: i128, slot: u32) {
-    let event: ClaimSlotEvent = ClaimSlotEvent { user, amount, slot };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("claim")), event);
+pub(crate) fn claim_slot(
+    e: &Env,
+    user: Address,
+    slot: u32,
+    amount: i128,
+    fee: i128,
+    asset_amount: i128,
+) {
+    let event: ClaimSlotEvent = ClaimSlotEvent {
+        user,
+        slot,
+        amount,
+        fee,
+        net_amount: amount - fee,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        total_requested_amount: read_total_requested_amount(e),
+        total_claimable_amount: read_total_claimable_amount(e),
+    };
+    publish(e, symbol_short!("claim"), event);
 }
 
 #[contracttype]
@@ -178,12 +658,39 @@ pub(crate) fn claim_slot(e: &Env, user: Address, amount: i128, slot: u32) {
 pub struct UnstakeInstantEvent {
     pub user: Address,
     pub amount: i128,
-}
-
-pub(crate) fn unstake_instant(e: &Env, user: Address, amount: i128) {
-    let event: UnstakeInstantEvent = UnstakeInstantEvent { user, amount };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("unstake_i")), event);
+    pub pump_amount: i128,
+    pub fee: i128,
+    // After the relayer fee, if any
+    pub net_amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub total_staking_amount: i128,
+    pub pending_stake_amount: i128,
+}
+
+pub(crate) fn unstake_instant(
+    e: &Env,
+    user: Address,
+    amount: i128,
+    pump_amount: i128,
+    fee: i128,
+    net_amount: i128,
+    asset_amount: i128,
+) {
+    let event: UnstakeInstantEvent = UnstakeInstantEvent {
+        user,
+        amount,
+        pump_amount,
+        fee,
+        net_amount,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        total_staking_amount: read_total_staking_amount(e),
+        pending_stake_amount: read_pending_stake_amount(e),
+    };
+    publish(e, symbol_short!("unstake_i"), event);
 }
 
 #[contracttype]
@@ -191,12 +698,64 @@ pub(crate) fn unstake_instant(e: &Env, user: Address, amount: i128) {
 pub struct ClaimAllEvent {
     pub user: Address,
     pub amount: i128,
+    pub fee: i128,
+    pub net_amount: i128,
+    pub asset_token: Address,
+    pub asset_amount: i128,
+    pub timestamp: u64,
+    pub total_requested_amount: i128,
+    pub total_claimable_amount: i128,
+}
+
+pub(crate) fn claim_all(e: &Env, user: Address, amount: i128, fee: i128, asset_amount: i128) {
+    let event: ClaimAllEvent = ClaimAllEvent {
+        user,
+        amount,
+        fee,
+        net_amount: amount - fee,
+        asset_token: read_asset_token_address(e),
+        asset_amount,
+        timestamp: e.ledger().timestamp(),
+        total_requested_amount: read_total_requested_amount(e),
+        total_claimable_amount: read_total_claimable_amount(e),
+    };
+    publish(e, symbol_short!("claim_all"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReconcileEvent {
+    pub admin: Address,
+    pub surplus: i128,
+    pub unallocated_amount: i128,
+}
+
+pub(crate) fn reconcile(e: &Env, admin: Address, surplus: i128, unallocated_amount: i128) {
+    let event: ReconcileEvent = ReconcileEvent {
+        admin,
+        surplus,
+        unallocated_amount,
+    };
+    publish(e, symbol_short!("reconcile"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RescueTokensEvent {
+    pub admin: Address,
+    pub token: Address,
+    pub to: Address,
+    pub amount: i128,
 }
 
-pub(crate) fn claim_all(e: &Env, user: Address, amount: i128) {
-    let event: ClaimAllEvent = ClaimAllEvent { user, amount };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("claim_all")), event);
+pub(crate) fn rescue_tokens(e: &Env, admin: Address, token: Address, to: Address, amount: i128) {
+    let event: RescueTokensEvent = RescueTokensEvent {
+        admin,
+        token,
+        to,
+        amount,
+    };
+    publish(e, symbol_short!("rescue"), event);
 }
 
 #[contracttype]
@@ -211,8 +770,7 @@ pub(crate) fn transfer_admin(e: &Env, previous_admin: Address, new_admin: Addres
         previous_admin,
         new_admin,
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("tra_admin")), event);
+    publish(e, symbol_short!("tra_admin"), event);
 }
 
 #[contracttype]
@@ -227,8 +785,7 @@ pub(crate) fn accept_admin(e: &Env, previous_admin: Address, new_admin: Address)
         previous_admin,
         new_admin,
     };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("acc_admin")), event);
+    publish(e, symbol_short!("acc_admin"), event);
 }
 
 #[contracttype]
@@ -239,8 +796,70 @@ pub struct RenounceAdminEvent {
 
 pub(crate) fn renounce_admin(e: &Env, admin: Address) {
     let event: RenounceAdminEvent = RenounceAdminEvent { admin };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("ren_admin")), event);
+    publish(e, symbol_short!("ren_admin"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnnounceUpgradeEvent {
+    pub admin: Address,
+    pub hash: BytesN<32>,
+    pub executable_after: u64,
+}
+
+pub(crate) fn announce_upgrade(e: &Env, admin: Address, hash: BytesN<32>, executable_after: u64) {
+    let event: AnnounceUpgradeEvent = AnnounceUpgradeEvent {
+        admin,
+        hash,
+        executable_after,
+    };
+    publish(e, symbol_short!("announce"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CancelUpgradeEvent {
+    pub admin: Address,
+    pub hash: BytesN<32>,
+}
+
+pub(crate) fn cancel_upgrade(e: &Env, admin: Address, hash: BytesN<32>) {
+    let event: CancelUpgradeEvent = CancelUpgradeEvent { admin, hash };
+    publish(e, symbol_short!("cancel_up"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeEvent {
+    pub admin: Address,
+    pub old_hash: Option<BytesN<32>>,
+    pub new_hash: BytesN<32>,
+}
+
+pub(crate) fn upgrade(e: &Env, admin: Address, old_hash: Option<BytesN<32>>, new_hash: BytesN<32>) {
+    let event: UpgradeEvent = UpgradeEvent {
+        admin,
+        old_hash,
+        new_hash,
+    };
+    publish(e, symbol_short!("upgrade"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigrateEvent {
+    pub admin: Address,
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+pub(crate) fn migrate(e: &Env, admin: Address, from_version: u32, to_version: u32) {
+    let event: MigrateEvent = MigrateEvent {
+        admin,
+        from_version,
+        to_version,
+    };
+    publish(e, symbol_short!("migrate"), event);
 }
 
 #[contracttype]
@@ -251,8 +870,42 @@ pub struct PausedEvent {
 
 pub(crate) fn paused(e: &Env, admin: Address) {
     let event: PausedEvent = PausedEvent { admin };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("paused")), event);
+    publish(e, symbol_short!("paused"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvariantViolatedEvent {
+    pub invariant: Invariant,
+    pub balance: i128,
+    pub total_staking_amount: i128,
+    pub total_requested_amount: i128,
+    pub total_claimable_amount: i128,
+    pub pending_stake_amount: i128,
+    pub collected_fee: i128,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn invariant_violated(
+    e: &Env,
+    invariant: Invariant,
+    balance: i128,
+    total_staking_amount: i128,
+    total_requested_amount: i128,
+    total_claimable_amount: i128,
+    pending_stake_amount: i128,
+    collected_fee: i128,
+) {
+    let event: InvariantViolatedEvent = InvariantViolatedEvent {
+        invariant,
+        balance,
+        total_staking_amount,
+        total_requested_amount,
+        total_claimable_amount,
+        pending_stake_amount,
+        collected_fee,
+    };
+    publish(e, symbol_short!("invariant"), event);
 }
 
 #[contracttype]
@@ -263,6 +916,88 @@ pub struct UnpausedEvent {
 
 pub(crate) fn unpaused(e: &Env, admin: Address) {
     let event: UnpausedEvent = UnpausedEvent { admin };
-    e.events()
-        .publish(("PumpBTCStaking", symbol_short!("unpaused")), event);
+    publish(e, symbol_short!("unpaused"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetKeeperTipEvent {
+    pub user: Address,
+    pub opted_in: bool,
+    pub keeper_tip: i128,
+}
+
+pub(crate) fn set_keeper_tip(e: &Env, user: Address, opted_in: bool, keeper_tip: i128) {
+    let event: SetKeeperTipEvent = SetKeeperTipEvent {
+        user,
+        opted_in,
+        keeper_tip,
+    };
+    publish(e, symbol_short!("set_tip"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimForEvent {
+    pub keeper: Address,
+    pub user: Address,
+    pub claimed: bool,
+    pub amount: i128,
+    pub fee: i128,
+    pub keeper_tip: i128,
+}
+
+pub(crate) fn claim_for(
+    e: &Env,
+    keeper: Address,
+    user: Address,
+    claimed: bool,
+    amount: i128,
+    fee: i128,
+    keeper_tip: i128,
+) {
+    let event: ClaimForEvent = ClaimForEvent {
+        keeper,
+        user,
+        claimed,
+        amount,
+        fee,
+        keeper_tip,
+    };
+    publish(e, symbol_short!("claim_for"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetIntentSignerEvent {
+    pub user: Address,
+    pub public_key: BytesN<32>,
+}
+
+pub(crate) fn set_intent_signer(e: &Env, user: Address, public_key: BytesN<32>) {
+    let event: SetIntentSignerEvent = SetIntentSignerEvent { user, public_key };
+    publish(e, symbol_short!("set_sign"), event);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubmitIntentEvent {
+    pub user: Address,
+    pub relayer: Address,
+    pub action: IntentAction,
+    pub amount: i128,
+    pub relayer_fee: i128,
+    pub nonce: u64,
+}
+
+pub(crate) fn submit_intent(e: &Env, relayer: Address, intent: StakingIntent) {
+    let event: SubmitIntentEvent = SubmitIntentEvent {
+        user: intent.user,
+        relayer,
+        action: intent.action,
+        amount: intent.amount,
+        relayer_fee: intent.relayer_fee,
+        nonce: intent.nonce,
+    };
+    publish(e, symbol_short!("intent"), event);
 }
@@ -0,0 +1,74 @@
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, BytesN, Env};
+
+use crate::error::PumpBTCStakingError;
+use crate::math::{pump_to_btc, Rounding};
+use crate::storage::{read_intent_nonce, read_intent_signer, write_intent_nonce};
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IntentAction {
+    Stake,
+    UnstakeRequest,
+    UnstakeInstant,
+}
+
+// Payload signed off-chain by the user's registered ed25519 key. The XDR
+// encoding of this struct is the exact message that gets signed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StakingIntent {
+    pub action: IntentAction,
+    pub user: Address,
+    // Only this relayer may submit the intent and collect its fee
+    pub relayer: Address,
+    // BTC (8 decimals) for `Stake`, pumpBTC for the unstake actions
+    pub amount: i128,
+    // BTC value (8 decimals) owed to the relayer, taken out of `amount`. It
+    // is paid in the asset, in whole asset units, except for `UnstakeRequest`
+    // where no asset moves and the relayer gets pumpBTC worth at least the fee.
+    pub relayer_fee: i128,
+    pub nonce: u64,
+    pub deadline: u64,
+    pub contract: Address,
+}
+
+pub fn verify_intent(
+    e: &Env,
+    relayer: &Address,
+    intent: &StakingIntent,
+    signature: &BytesN<64>,
+) -> Result<(), PumpBTCStakingError> {
+    if intent.contract != e.current_contract_address() {
+        return Err(PumpBTCStakingError::InvalidIntentTarget);
+    }
+    if intent.relayer != *relayer {
+        return Err(PumpBTCStakingError::InvalidIntentTarget);
+    }
+    if e.ledger().timestamp() > intent.deadline {
+        return Err(PumpBTCStakingError::IntentExpired);
+    }
+    let value = match intent.action {
+        IntentAction::Stake => intent.amount,
+        IntentAction::UnstakeRequest | IntentAction::UnstakeInstant => {
+            pump_to_btc(e, intent.amount, Rounding::Down)?
+        }
+    };
+    if intent.relayer_fee < 0 || intent.relayer_fee >= value {
+        return Err(PumpBTCStakingError::InvalidRelayerFee);
+    }
+
+    let nonce = read_intent_nonce(e, &intent.user);
+    if intent.nonce != nonce {
+        return Err(PumpBTCStakingError::InvalidIntentNonce);
+    }
+
+    let public_key =
+        read_intent_signer(e, &intent.user).ok_or(PumpBTCStakingError::NoIntentSignerSet)?;
+
+    // Panics if the signature does not match
+    e.crypto()
+        .ed25519_verify(&public_key, &intent.clone().to_xdr(e), signature);
+
+    write_intent_nonce(e, &intent.user, nonce + 1);
+    Ok(())
+}
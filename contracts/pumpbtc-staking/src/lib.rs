@@ -4,8 +4,13 @@ mod auth;
 mod contract;
 mod error;
 mod event;
+mod intent;
+mod lock;
 mod math;
+mod migration;
+mod solvency;
 mod storage;
+mod tokens;
 mod utils;
 mod tests;
 
@@ -0,0 +1,43 @@
+use soroban_sdk::contracttype;
+
+use crate::storage::SECONDS_PER_DAY;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LockTier {
+    Days30,
+    Days90,
+    Days180,
+}
+
+impl LockTier {
+    pub fn duration(self) -> u64 {
+        match self {
+            LockTier::Days30 => 30 * SECONDS_PER_DAY,
+            LockTier::Days90 => 90 * SECONDS_PER_DAY,
+            LockTier::Days180 => 180 * SECONDS_PER_DAY,
+        }
+    }
+
+    // Rewards and points multiplier in bps
+    pub fn multiplier(self) -> i128 {
+        match self {
+            LockTier::Days30 => 11000,
+            LockTier::Days90 => 12500,
+            LockTier::Days180 => 15000,
+        }
+    }
+}
+
+// pumpBTC committed until `unlock_time`. The `amount` stays locked in the
+// user's balance and `boost` earns rewards and points on top of it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockPosition {
+    pub id: u32,
+    pub tier: LockTier,
+    pub amount: i128,
+    pub boost: i128,
+    pub start_time: u64,
+    pub unlock_time: u64,
+}
@@ -1,7 +1,11 @@
 use soroban_sdk::Env;
 
 use crate::error::PumpBTCStakingError;
-use crate::storage::read_asset_decimal;
+use crate::storage::{
+    read_asset_decimal, read_claim_ratio, read_exchange_rate, read_instant_fee_curve,
+    read_instant_unstake_fee, read_liquidity_buffer_amount, read_liquidity_buffer_bps,
+    read_pending_stake_amount, read_pump_token_decimal, read_total_staking_amount, RATE_PRECISION,
+};
 
 pub fn check_nonnegative_amount(amount: i128) -> Result<(), PumpBTCStakingError> {
     if amount <= 0 {
@@ -10,15 +14,179 @@ pub fn check_nonnegative_amount(amount: i128) -> Result<(), PumpBTCStakingError>
     Ok(())
 }
 
-pub fn adjust_amount(e: &Env, amount: i128) -> Result<i128, PumpBTCStakingError> {
+// Direction to round a decimal conversion in. Amounts leaving the contract
+// round down and amounts owed to it round up, so dust always stays behind.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+fn decimal_factor(decimal_diff: u32) -> Result<i128, PumpBTCStakingError> {
+    10i128
+        .checked_pow(decimal_diff)
+        .ok_or(PumpBTCStakingError::MathOverflow)
+}
+
+fn div_rounding(
+    amount: i128,
+    divisor: i128,
+    rounding: Rounding,
+) -> Result<i128, PumpBTCStakingError> {
+    let quotient = amount
+        .checked_div_euclid(divisor)
+        .ok_or(PumpBTCStakingError::MathOverflow)?;
+    if rounding == Rounding::Up && amount.rem_euclid(divisor) != 0 {
+        safe_add(quotient, 1)
+    } else {
+        Ok(quotient)
+    }
+}
+
+// Converts a pumpBTC (8 decimals) amount into asset token units
+pub fn adjust_amount(
+    e: &Env,
+    amount: i128,
+    rounding: Rounding,
+) -> Result<i128, PumpBTCStakingError> {
+    let asset_decimal = read_asset_decimal(e);
+    if asset_decimal >= 8 {
+        safe_mul(amount, decimal_factor(asset_decimal - 8)?)
+    } else {
+        div_rounding(amount, decimal_factor(8 - asset_decimal)?, rounding)
+    }
+}
+
+// Converts a pumpBTC amount into asset token units, rejecting amounts the
+// asset cannot represent instead of rounding them
+pub fn adjust_amount_exact(e: &Env, amount: i128) -> Result<i128, PumpBTCStakingError> {
+    let asset_amount = adjust_amount(e, amount, Rounding::Down)?;
+    if to_pump_amount(e, asset_amount, Rounding::Down)? != amount {
+        return Err(PumpBTCStakingError::AmountNotRepresentable);
+    }
+    Ok(asset_amount)
+}
+
+// Converts an asset token amount into pumpBTC units, rejecting amounts that
+// are not a whole number of satoshis
+pub fn to_pump_amount_exact(e: &Env, asset_amount: i128) -> Result<i128, PumpBTCStakingError> {
+    let amount = to_pump_amount(e, asset_amount, Rounding::Down)?;
+    if adjust_amount(e, amount, Rounding::Down)? != asset_amount {
+        return Err(PumpBTCStakingError::AmountNotRepresentable);
+    }
+    Ok(amount)
+}
+
+// Converts an asset token amount into pumpBTC (8 decimals) units
+pub fn to_pump_amount(
+    e: &Env,
+    asset_amount: i128,
+    rounding: Rounding,
+) -> Result<i128, PumpBTCStakingError> {
     let asset_decimal = read_asset_decimal(e);
-    if asset_decimal == 8 {
-        Ok(amount)
+    if asset_decimal <= 8 {
+        safe_mul(asset_amount, decimal_factor(8 - asset_decimal)?)
     } else {
-        let decimal_diff = safe_sub(asset_decimal as i128, 8)?;
-        let factor = 10i128.pow(decimal_diff as u32);
-        safe_mul(amount, factor)
+        div_rounding(asset_amount, decimal_factor(asset_decimal - 8)?, rounding)
+    }
+}
+
+// BTC value (8 decimals) of a pumpBTC amount at the current exchange rate
+pub fn pump_to_btc(e: &Env, amount: i128, rounding: Rounding) -> Result<i128, PumpBTCStakingError> {
+    div_rounding(
+        safe_mul(amount, read_exchange_rate(e))?,
+        RATE_PRECISION,
+        rounding,
+    )
+}
+
+// pumpBTC amount worth a BTC value (8 decimals) at the current exchange rate
+pub fn btc_to_pump(e: &Env, value: i128, rounding: Rounding) -> Result<i128, PumpBTCStakingError> {
+    div_rounding(
+        safe_mul(value, RATE_PRECISION)?,
+        read_exchange_rate(e),
+        rounding,
+    )
+}
+
+// BTC still owed on an unstake request booked at `request_ratio`, after the
+// losses reported since
+pub fn apply_claim_ratio(
+    e: &Env,
+    amount: i128,
+    request_ratio: i128,
+) -> Result<i128, PumpBTCStakingError> {
+    div_rounding(
+        safe_mul(amount, read_claim_ratio(e))?,
+        request_ratio,
+        Rounding::Down,
+    )
+}
+
+// Scales an aggregate by `new_ratio / old_ratio`. Totals round up so they
+// always cover the sum of the individually rounded claims.
+pub fn scale_total(
+    amount: i128,
+    new_ratio: i128,
+    old_ratio: i128,
+) -> Result<i128, PumpBTCStakingError> {
+    div_rounding(safe_mul(amount, new_ratio)?, old_ratio, Rounding::Up)
+}
+
+// Converts a pumpBTC (8 decimals) amount into pump token units. These only
+// differ for a Stellar Asset Contract pumpBTC, which has 7 decimals.
+pub fn pump_token_amount_exact(e: &Env, amount: i128) -> Result<i128, PumpBTCStakingError> {
+    let factor = decimal_factor(8 - read_pump_token_decimal(e))?;
+    if amount.rem_euclid(factor) != 0 {
+        return Err(PumpBTCStakingError::AmountNotRepresentable);
     }
+    safe_div(amount, factor)
+}
+
+// Rounds a pumpBTC amount to a whole number of pump token units
+pub fn round_to_pump_unit(
+    e: &Env,
+    amount: i128,
+    rounding: Rounding,
+) -> Result<i128, PumpBTCStakingError> {
+    let factor = decimal_factor(8 - read_pump_token_decimal(e))?;
+    safe_mul(div_rounding(amount, factor, rounding)?, factor)
+}
+
+pub fn instant_unstake_fee(e: &Env, amount: i128) -> Result<i128, PumpBTCStakingError> {
+    let fee_rate = match read_instant_fee_curve(e) {
+        None => read_instant_unstake_fee(e),
+        Some(curve) => {
+            // Share of the pending stake buffer this unstake uses up, in bps
+            let pending_stake_amount = read_pending_stake_amount(e);
+            let utilisation = if amount >= pending_stake_amount {
+                10000
+            } else {
+                safe_div(safe_mul(amount, 10000)?, pending_stake_amount)?
+            };
+            let fee_range = safe_sub(curve.max_fee, curve.base_fee)?;
+            safe_add(
+                curve.base_fee,
+                safe_div(safe_mul(fee_range, utilisation)?, 10000)?,
+            )?
+        }
+    };
+    safe_div(safe_mul(amount, fee_rate)?, 10000)
+}
+
+// Part of the pending stake that operator withdrawals must leave behind for
+// instant unstakes: the larger of the absolute and the TVL-based buffer
+pub fn reserved_liquidity(e: &Env) -> Result<i128, PumpBTCStakingError> {
+    let buffer_amount = read_liquidity_buffer_amount(e);
+    let buffer_from_tvl = safe_div(
+        safe_mul(
+            pump_to_btc(e, read_total_staking_amount(e), Rounding::Up)?,
+            read_liquidity_buffer_bps(e),
+        )?,
+        10000,
+    )?;
+    let buffer = buffer_amount.max(buffer_from_tvl);
+    Ok(buffer.min(read_pending_stake_amount(e)))
 }
 
 pub fn safe_mul(a: i128, b: i128) -> Result<i128, PumpBTCStakingError> {
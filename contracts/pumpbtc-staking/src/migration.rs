@@ -0,0 +1,32 @@
+use soroban_sdk::Env;
+
+use crate::error::PumpBTCStakingError;
+use crate::storage::{read_schema_version, write_schema_version, SCHEMA_VERSION};
+
+// Brings storage written by an older contract version up to SCHEMA_VERSION one
+// step at a time. Steps must be safe to run again, and calling this on an
+// up-to-date schema is a no-op.
+pub fn migrate(e: &Env, from_version: u32) -> Result<u32, PumpBTCStakingError> {
+    let mut version = read_schema_version(e);
+    if from_version != version || version > SCHEMA_VERSION {
+        return Err(PumpBTCStakingError::SchemaVersionMismatch);
+    }
+
+    while version < SCHEMA_VERSION {
+        match version {
+            // Pre-versioning layout is unchanged, only the version is recorded
+            0 => {}
+            // Every key added since version 1 reads as its default while
+            // unset: a 1:1 exchange rate and claim ratio, an 8 decimal
+            // pumpBTC minted through the pump token and no daily obligations
+            // for requests booked before them. Nothing needs rewriting. The
+            // fixed withdraw window entries are superseded by bucketed
+            // outflows, so the rolling window starts out empty.
+            1 => {}
+            _ => return Err(PumpBTCStakingError::SchemaVersionMismatch),
+        }
+        version += 1;
+        write_schema_version(e, version);
+    }
+    Ok(version)
+}
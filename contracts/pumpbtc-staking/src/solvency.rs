@@ -0,0 +1,114 @@
+use soroban_sdk::{contracttype, token, Env, Vec};
+
+use crate::error::PumpBTCStakingError;
+use crate::math::{adjust_amount, safe_add, safe_sub, scale_total, Rounding};
+use crate::storage::{
+    read_asset_token_address, read_claim_ratio, read_collected_fee, read_daily_claim_ratio,
+    read_daily_claimed_amount, read_daily_requested_amount, read_pending_stake_amount,
+    read_reward_reserve, read_total_claimable_amount, read_total_requested_amount,
+    read_unallocated_amount, DailyObligation, MAX_DATE_SLOT,
+};
+use crate::utils::{get_day, get_day_start_time};
+
+// Backing of the contract, all amounts in asset token units
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SolvencyReport {
+    pub balance: i128,
+    pub pending_stake_amount: i128,
+    pub total_claimable_amount: i128,
+    pub collected_fee: i128,
+    pub unallocated_amount: i128,
+    pub reward_reserve: i128,
+    pub liabilities: i128,
+    // Negative when the balance does not cover the liabilities
+    pub surplus: i128,
+}
+
+pub fn solvency_report(e: &Env) -> Result<SolvencyReport, PumpBTCStakingError> {
+    let asset_token = read_asset_token_address(e);
+    let asset_client = token::Client::new(e, &asset_token);
+    let balance = asset_client.balance(&e.current_contract_address());
+
+    // Liabilities round up so that the surplus is never overstated
+    let pending_stake_amount = adjust_amount(e, read_pending_stake_amount(e), Rounding::Up)?;
+    let total_claimable_amount = adjust_amount(e, read_total_claimable_amount(e), Rounding::Up)?;
+    let collected_fee = adjust_amount(e, read_collected_fee(e), Rounding::Up)?;
+    let unallocated_amount = read_unallocated_amount(e);
+    let reward_reserve = adjust_amount(e, read_reward_reserve(e), Rounding::Up)?;
+
+    let liabilities = safe_add(
+        safe_add(pending_stake_amount, total_claimable_amount)?,
+        safe_add(safe_add(collected_fee, unallocated_amount)?, reward_reserve)?,
+    )?;
+
+    Ok(SolvencyReport {
+        balance,
+        pending_stake_amount,
+        total_claimable_amount,
+        collected_fee,
+        unallocated_amount,
+        reward_reserve,
+        liabilities,
+        surplus: safe_sub(balance, liabilities)?,
+    })
+}
+
+// Requested and claimed amounts of a maturity day, scaled by the losses
+// reported since they were booked
+pub fn daily_amounts(e: &Env, day: u64) -> Result<(i128, i128), PumpBTCStakingError> {
+    let requested_amount = read_daily_requested_amount(e, day);
+    let claimed_amount = read_daily_claimed_amount(e, day);
+
+    let claim_ratio = read_claim_ratio(e);
+    let day_ratio = read_daily_claim_ratio(e, day);
+    if claim_ratio == day_ratio {
+        return Ok((requested_amount, claimed_amount));
+    }
+    Ok((
+        scale_total(requested_amount, claim_ratio, day_ratio)?,
+        scale_total(claimed_amount, claim_ratio, day_ratio)?,
+    ))
+}
+
+pub fn daily_obligation(e: &Env, day: u64) -> Result<DailyObligation, PumpBTCStakingError> {
+    let (requested_amount, claimed_amount) = daily_amounts(e, day)?;
+
+    // Requests made before daily tracking existed are only seen when claimed
+    let outstanding_amount = safe_sub(requested_amount, claimed_amount)?.max(0);
+
+    Ok(DailyObligation {
+        day,
+        start_time: get_day_start_time(day),
+        requested_amount,
+        claimed_amount,
+        outstanding_amount,
+    })
+}
+
+// Obligations maturing today and on each of the following days. Requests
+// never mature more than MAX_DATE_SLOT - 1 days ahead, so `days` is capped.
+pub fn funding_schedule(e: &Env, days: u32) -> Result<Vec<DailyObligation>, PumpBTCStakingError> {
+    let today = get_day(e.ledger().timestamp());
+
+    let mut schedule = Vec::new(e);
+    for offset in 0..days.min(MAX_DATE_SLOT) {
+        schedule.push_back(daily_obligation(e, today + offset as u64)?);
+    }
+    Ok(schedule)
+}
+
+// Requested amount maturing today or earlier that the operator has not yet
+// deposited, in pumpBTC units
+pub fn funding_shortfall(e: &Env) -> Result<i128, PumpBTCStakingError> {
+    let today = get_day(e.ledger().timestamp());
+
+    let mut future_amount: i128 = 0;
+    for offset in 1..MAX_DATE_SLOT {
+        let obligation = daily_obligation(e, today + offset as u64)?;
+        future_amount = safe_add(future_amount, obligation.outstanding_amount)?;
+    }
+
+    let due_amount = safe_sub(read_total_requested_amount(e), future_amount)?;
+    Ok(safe_sub(due_amount, read_total_claimable_amount(e))?.max(0))
+}
@@ -1,14 +1,34 @@
-use soroban_sdk::{contracttype, Address, Env};
+use soroban_sdk::{contracttype, Address, BytesN, Env, Vec};
+
+use crate::lock::LockPosition;
 
 pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
 pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
 pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub(crate) const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
 pub(crate) const SECONDS_PER_DAY: u64 = 86400;
 pub(crate) const UTC_OFFSET: u64 = 8 * 3600;
 
 pub(crate) const MAX_DATE_SLOT: u32 = 10;
 
+// Bump whenever `DataKey`, `StakingDataKey` or `PendingDataKey` change shape
+// and add the matching step to `migration::migrate`
+pub(crate) const SCHEMA_VERSION: u32 = 2;
+
+// Exchange rates are asset (8 decimals) per pumpBTC, scaled by this factor
+pub(crate) const RATE_PRECISION: i128 = 1_000_000_000_000_000_000;
+
+// Most exchange rate updates returned by one history query
+pub(crate) const MAX_RATE_HISTORY: u32 = 30;
+
+// Time an announced upgrade must wait before it can be executed
+pub(crate) const UPGRADE_DELAY: u64 = 3 * SECONDS_PER_DAY;
+
+// Number of buckets the operator withdrawal window is split into
+pub(crate) const WITHDRAW_WINDOW_BUCKETS: u64 = 24;
+
 // Instance storage keys - for contract configuration and management data
 #[derive(Clone)]
 #[contracttype]
@@ -18,11 +38,107 @@ pub enum DataKey {
     Paused,
     Operator,
     PumpTokenAddress,
+    PumpTokenKind,
+    PumpTokenDecimal,
     AssetTokenAddress,
     AssetDecimal,
     NormalUnstakeFee,
     InstantUnstakeFee,
     OnlyAllowStake,
+    Treasury,
+    FeeSplits,
+    InstantFeeCurve,
+    LiquidityBufferAmount,
+    LiquidityBufferBps,
+    WithdrawLimit,
+    WithdrawWindow,
+    OperatorPaused,
+    InvariantViolation,
+    SchemaVersion,
+    WasmHash,
+    PendingUpgrade,
+    ExchangeRate,
+    ExchangeRateUpdatedAt,
+    ExchangeRateBounds,
+    RateOracle,
+    LockPenalty,
+    ClaimRatio,
+}
+
+// How pumpBTC is minted and burned: through the minter role of the custom
+// pump token, or as the admin of a Stellar Asset Contract
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PumpTokenKind {
+    Minter,
+    StellarAsset,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PendingUpgrade {
+    pub hash: BytesN<32>,
+    pub executable_after: u64,
+}
+
+// Accounting invariant found broken by `enforce_invariants`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Invariant {
+    NegativeTotal,
+    ClaimableExceedsBalance,
+    // More was claimed than requested, so the outstanding requests went negative
+    NegativeRequested,
+}
+
+// Instant unstake fee rising linearly from `base_fee` to `max_fee` as the
+// unstake uses up more of the pending stake buffer
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct InstantFeeCurve {
+    pub base_fee: i128,
+    pub max_fee: i128,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeSplit {
+    pub recipient: Address,
+    pub bps: i128,
+}
+
+// Limits on exchange rate updates. A zero `max_change_bps` freezes the rate.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ExchangeRateBounds {
+    pub max_change_bps: i128,
+    pub min_update_interval: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ExchangeRateUpdate {
+    pub rate: i128,
+    pub timestamp: u64,
+}
+
+// Unstake obligations maturing on one UTC+8 day, see `get_day`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DailyObligation {
+    pub day: u64,
+    pub start_time: u64,
+    pub requested_amount: i128,
+    pub claimed_amount: i128,
+    pub outstanding_amount: i128,
+}
+
+// Operator outflow booked in the withdrawal window bucket closing at `bucket_end`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WithdrawOutflow {
+    pub bucket_end: u64,
+    pub amount: i128,
 }
 
 // Persistent storage keys - for long-term global state
@@ -35,6 +151,29 @@ pub enum StakingDataKey {
     TotalClaimableAmount,
     PendingStakeAmount,
     CollectedFee,
+    CollectedNormalUnstakeFee,
+    CollectedInstantUnstakeFee,
+    LifetimeNormalUnstakeFee,
+    LifetimeInstantUnstakeFee,
+    CollectedLockPenalty,
+    LifetimeLockPenalty,
+    OwedLockPenalty,
+    WithdrawOutflows,
+    UnallocatedAmount,
+    RewardReserve,
+    DailyRequestedAmount(u64),
+    DailyClaimedAmount(u64),
+    DailyClaimRatio(u64),
+    ExchangeRateHistoryCount,
+    ExchangeRateHistory(u32),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum FeeBucket {
+    NormalUnstake,
+    InstantUnstake,
+    LockPenalty,
 }
 
 // Temporary storage keys - for time-limited user data
@@ -43,6 +182,19 @@ pub enum StakingDataKey {
 pub enum PendingDataKey {
     PendingUnstakeTime(Address, u32),
     PendingUnstakeAmount(Address, u32),
+    RequestClaimRatio(Address, u32),
+}
+
+// Persistent storage keys - for long-lived per-address settings
+#[derive(Clone)]
+#[contracttype]
+pub enum UserDataKey {
+    WithdrawDestination(Address),
+    KeeperTip(Address),
+    IntentSigner(Address),
+    IntentNonce(Address),
+    LockPosition(Address, u32),
+    NextLockId(Address),
 }
 
 pub fn read_pump_token_address(e: &Env) -> Address {
@@ -58,6 +210,30 @@ pub fn write_pump_token_address(e: &Env, address: &Address) {
         .set(&DataKey::PumpTokenAddress, address);
 }
 
+pub fn read_pump_token_kind(e: &Env) -> PumpTokenKind {
+    e.storage()
+        .instance()
+        .get(&DataKey::PumpTokenKind)
+        .unwrap_or(PumpTokenKind::Minter)
+}
+
+pub fn write_pump_token_kind(e: &Env, kind: PumpTokenKind) {
+    e.storage().instance().set(&DataKey::PumpTokenKind, &kind);
+}
+
+pub fn read_pump_token_decimal(e: &Env) -> u32 {
+    e.storage()
+        .instance()
+        .get(&DataKey::PumpTokenDecimal)
+        .unwrap_or(8)
+}
+
+pub fn write_pump_token_decimal(e: &Env, decimal: u32) {
+    e.storage()
+        .instance()
+        .set(&DataKey::PumpTokenDecimal, &decimal);
+}
+
 pub fn read_asset_token_address(e: &Env) -> Address {
     e.storage()
         .instance()
@@ -101,6 +277,18 @@ pub fn write_normal_unstake_fee(e: &Env, fee: i128) {
     e.storage().instance().set(&DataKey::NormalUnstakeFee, &fee);
 }
 
+// Share of a lock position forfeited on early exit, in bps
+pub fn read_lock_penalty(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::LockPenalty)
+        .unwrap_or(0)
+}
+
+pub fn write_lock_penalty(e: &Env, penalty: i128) {
+    e.storage().instance().set(&DataKey::LockPenalty, &penalty);
+}
+
 pub fn read_instant_unstake_fee(e: &Env) -> i128 {
     e.storage()
         .instance()
@@ -114,6 +302,108 @@ pub fn write_instant_unstake_fee(e: &Env, fee: i128) {
         .set(&DataKey::InstantUnstakeFee, &fee);
 }
 
+pub fn read_instant_fee_curve(e: &Env) -> Option<InstantFeeCurve> {
+    e.storage().instance().get(&DataKey::InstantFeeCurve)
+}
+
+pub fn write_instant_fee_curve(e: &Env, curve: &InstantFeeCurve) {
+    e.storage().instance().set(&DataKey::InstantFeeCurve, curve);
+}
+
+pub fn remove_instant_fee_curve(e: &Env) {
+    e.storage().instance().remove(&DataKey::InstantFeeCurve);
+}
+
+pub fn read_liquidity_buffer_amount(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::LiquidityBufferAmount)
+        .unwrap_or(0)
+}
+
+pub fn write_liquidity_buffer_amount(e: &Env, amount: i128) {
+    e.storage()
+        .instance()
+        .set(&DataKey::LiquidityBufferAmount, &amount);
+}
+
+pub fn read_liquidity_buffer_bps(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::LiquidityBufferBps)
+        .unwrap_or(0)
+}
+
+pub fn write_liquidity_buffer_bps(e: &Env, bps: i128) {
+    e.storage()
+        .instance()
+        .set(&DataKey::LiquidityBufferBps, &bps);
+}
+
+pub fn is_withdraw_destination(e: &Env, destination: &Address) -> bool {
+    let key = UserDataKey::WithdrawDestination(destination.clone());
+    if !e.storage().persistent().has(&key) {
+        return false;
+    }
+    e.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+    true
+}
+
+pub fn write_withdraw_destination(e: &Env, destination: &Address, allowed: bool) {
+    let key = UserDataKey::WithdrawDestination(destination.clone());
+    if allowed {
+        e.storage().persistent().set(&key, &true);
+        e.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    } else {
+        e.storage().persistent().remove(&key);
+    }
+}
+
+pub fn read_withdraw_limit(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::WithdrawLimit)
+        .unwrap_or(0)
+}
+
+pub fn write_withdraw_limit(e: &Env, limit: i128) {
+    e.storage().instance().set(&DataKey::WithdrawLimit, &limit);
+}
+
+pub fn read_withdraw_window(e: &Env) -> u64 {
+    e.storage()
+        .instance()
+        .get(&DataKey::WithdrawWindow)
+        .unwrap_or(SECONDS_PER_DAY)
+}
+
+pub fn write_withdraw_window(e: &Env, window: u64) {
+    e.storage()
+        .instance()
+        .set(&DataKey::WithdrawWindow, &window);
+}
+
+pub fn read_operator_paused(e: &Env) -> bool {
+    e.storage()
+        .instance()
+        .get(&DataKey::OperatorPaused)
+        .unwrap_or(false)
+}
+
+pub fn write_operator_paused(e: &Env, paused: bool) {
+    e.storage()
+        .instance()
+        .set(&DataKey::OperatorPaused, &paused);
+}
+
 pub fn read_only_allow_stake(e: &Env) -> bool {
     e.storage()
         .instance()
@@ -127,6 +417,43 @@ pub fn write_only_allow_stake(e: &Env, only_allow_stake: bool) {
         .set(&DataKey::OnlyAllowStake, &only_allow_stake);
 }
 
+// Only known once the contract has been upgraded through `execute_upgrade`
+pub fn read_wasm_hash(e: &Env) -> Option<BytesN<32>> {
+    e.storage().instance().get(&DataKey::WasmHash)
+}
+
+pub fn write_wasm_hash(e: &Env, hash: &BytesN<32>) {
+    e.storage().instance().set(&DataKey::WasmHash, hash);
+}
+
+pub fn read_pending_upgrade(e: &Env) -> Option<PendingUpgrade> {
+    e.storage().instance().get(&DataKey::PendingUpgrade)
+}
+
+pub fn write_pending_upgrade(e: &Env, pending_upgrade: &PendingUpgrade) {
+    e.storage()
+        .instance()
+        .set(&DataKey::PendingUpgrade, pending_upgrade);
+}
+
+pub fn remove_pending_upgrade(e: &Env) {
+    e.storage().instance().remove(&DataKey::PendingUpgrade);
+}
+
+// Deployments from before versioning have no record and read as 0
+pub fn read_schema_version(e: &Env) -> u32 {
+    e.storage()
+        .instance()
+        .get(&DataKey::SchemaVersion)
+        .unwrap_or(0)
+}
+
+pub fn write_schema_version(e: &Env, version: u32) {
+    e.storage()
+        .instance()
+        .set(&DataKey::SchemaVersion, &version);
+}
+
 pub fn read_paused(e: &Env) -> bool {
     e.storage()
         .instance()
@@ -138,6 +465,39 @@ pub fn write_paused(e: &Env, paused: bool) {
     e.storage().instance().set(&DataKey::Paused, &paused);
 }
 
+pub fn read_treasury(e: &Env) -> Option<Address> {
+    e.storage().instance().get(&DataKey::Treasury)
+}
+
+pub fn write_treasury(e: &Env, treasury: &Address) {
+    e.storage().instance().set(&DataKey::Treasury, treasury);
+}
+
+pub fn read_fee_splits(e: &Env) -> Vec<FeeSplit> {
+    e.storage()
+        .instance()
+        .get(&DataKey::FeeSplits)
+        .unwrap_or(Vec::new(e))
+}
+
+pub fn write_fee_splits(e: &Env, fee_splits: &Vec<FeeSplit>) {
+    e.storage().instance().set(&DataKey::FeeSplits, fee_splits);
+}
+
+pub fn read_invariant_violation(e: &Env) -> Option<Invariant> {
+    e.storage().instance().get(&DataKey::InvariantViolation)
+}
+
+pub fn write_invariant_violation(e: &Env, invariant: Invariant) {
+    e.storage()
+        .instance()
+        .set(&DataKey::InvariantViolation, &invariant);
+}
+
+pub fn remove_invariant_violation(e: &Env) {
+    e.storage().instance().remove(&DataKey::InvariantViolation);
+}
+
 pub fn read_total_staking_amount(e: &Env) -> i128 {
     e.storage()
         .persistent()
@@ -216,6 +576,227 @@ pub fn write_collected_fee(e: &Env, fee: i128) {
         .set(&StakingDataKey::CollectedFee, &fee);
 }
 
+pub fn read_withdraw_outflows(e: &Env) -> Vec<WithdrawOutflow> {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::WithdrawOutflows)
+        .unwrap_or(Vec::new(e))
+}
+
+pub fn write_withdraw_outflows(e: &Env, outflows: &Vec<WithdrawOutflow>) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::WithdrawOutflows, outflows);
+}
+
+// Surplus recorded by `reconcile`, in asset token units
+pub fn read_unallocated_amount(e: &Env) -> i128 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::UnallocatedAmount)
+        .unwrap_or(0)
+}
+
+pub fn write_unallocated_amount(e: &Env, amount: i128) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::UnallocatedAmount, &amount);
+}
+
+// Rewards deposited for pumpBTC holders and not yet claimed, in pumpBTC units
+pub fn read_reward_reserve(e: &Env) -> i128 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::RewardReserve)
+        .unwrap_or(0)
+}
+
+pub fn write_reward_reserve(e: &Env, amount: i128) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::RewardReserve, &amount);
+}
+
+// Early unlock penalties the operator still has to deposit, in BTC. The BTC
+// backing the burned pumpBTC stays in custody until then.
+pub fn read_owed_lock_penalty(e: &Env) -> i128 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::OwedLockPenalty)
+        .unwrap_or(0)
+}
+
+pub fn write_owed_lock_penalty(e: &Env, amount: i128) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::OwedLockPenalty, &amount);
+}
+
+// Amounts requested and claimed per maturity day
+pub fn read_daily_requested_amount(e: &Env, day: u64) -> i128 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::DailyRequestedAmount(day))
+        .unwrap_or(0)
+}
+
+pub fn write_daily_requested_amount(e: &Env, day: u64, amount: i128) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::DailyRequestedAmount(day), &amount);
+}
+
+pub fn read_daily_claimed_amount(e: &Env, day: u64) -> i128 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::DailyClaimedAmount(day))
+        .unwrap_or(0)
+}
+
+pub fn write_daily_claimed_amount(e: &Env, day: u64, amount: i128) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::DailyClaimedAmount(day), &amount);
+}
+
+// Claim ratio when the day's amounts were last booked. Days booked before any
+// loss was reported have none and start from the full ratio.
+pub fn read_daily_claim_ratio(e: &Env, day: u64) -> i128 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::DailyClaimRatio(day))
+        .unwrap_or(RATE_PRECISION)
+}
+
+pub fn write_daily_claim_ratio(e: &Env, day: u64, ratio: i128) {
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::DailyClaimRatio(day), &ratio);
+}
+
+pub fn read_exchange_rate(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::ExchangeRate)
+        .unwrap_or(RATE_PRECISION)
+}
+
+pub fn write_exchange_rate(e: &Env, rate: i128) {
+    e.storage().instance().set(&DataKey::ExchangeRate, &rate);
+}
+
+// Share of unstake claims still paid after reported losses, scaled by
+// RATE_PRECISION. Every loss multiplies it down.
+pub fn read_claim_ratio(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::ClaimRatio)
+        .unwrap_or(RATE_PRECISION)
+}
+
+pub fn write_claim_ratio(e: &Env, ratio: i128) {
+    e.storage().instance().set(&DataKey::ClaimRatio, &ratio);
+}
+
+pub fn read_exchange_rate_updated_at(e: &Env) -> u64 {
+    e.storage()
+        .instance()
+        .get(&DataKey::ExchangeRateUpdatedAt)
+        .unwrap_or(0)
+}
+
+pub fn write_exchange_rate_updated_at(e: &Env, timestamp: u64) {
+    e.storage()
+        .instance()
+        .set(&DataKey::ExchangeRateUpdatedAt, &timestamp);
+}
+
+pub fn read_exchange_rate_bounds(e: &Env) -> ExchangeRateBounds {
+    e.storage()
+        .instance()
+        .get(&DataKey::ExchangeRateBounds)
+        .unwrap_or(ExchangeRateBounds {
+            max_change_bps: 0,
+            min_update_interval: 0,
+        })
+}
+
+pub fn write_exchange_rate_bounds(e: &Env, bounds: &ExchangeRateBounds) {
+    e.storage()
+        .instance()
+        .set(&DataKey::ExchangeRateBounds, bounds);
+}
+
+pub fn read_rate_oracle(e: &Env) -> Option<Address> {
+    e.storage().instance().get(&DataKey::RateOracle)
+}
+
+pub fn write_rate_oracle(e: &Env, oracle: &Address) {
+    e.storage().instance().set(&DataKey::RateOracle, oracle);
+}
+
+// Exchange rate updates, indexed from 0 in the order they were made
+pub fn read_exchange_rate_history_count(e: &Env) -> u32 {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::ExchangeRateHistoryCount)
+        .unwrap_or(0)
+}
+
+pub fn read_exchange_rate_update(e: &Env, index: u32) -> Option<ExchangeRateUpdate> {
+    e.storage()
+        .persistent()
+        .get(&StakingDataKey::ExchangeRateHistory(index))
+}
+
+pub fn push_exchange_rate_update(e: &Env, update: &ExchangeRateUpdate) {
+    let count = read_exchange_rate_history_count(e);
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::ExchangeRateHistory(count), update);
+    e.storage()
+        .persistent()
+        .set(&StakingDataKey::ExchangeRateHistoryCount, &(count + 1));
+}
+
+// Uncollected fee of one bucket, reset by `collect_fee`
+pub fn read_bucket_collected_fee(e: &Env, bucket: FeeBucket) -> i128 {
+    let key = match bucket {
+        FeeBucket::NormalUnstake => StakingDataKey::CollectedNormalUnstakeFee,
+        FeeBucket::InstantUnstake => StakingDataKey::CollectedInstantUnstakeFee,
+        FeeBucket::LockPenalty => StakingDataKey::CollectedLockPenalty,
+    };
+    e.storage().persistent().get(&key).unwrap_or(0)
+}
+
+pub fn write_bucket_collected_fee(e: &Env, bucket: FeeBucket, fee: i128) {
+    let key = match bucket {
+        FeeBucket::NormalUnstake => StakingDataKey::CollectedNormalUnstakeFee,
+        FeeBucket::InstantUnstake => StakingDataKey::CollectedInstantUnstakeFee,
+        FeeBucket::LockPenalty => StakingDataKey::CollectedLockPenalty,
+    };
+    e.storage().persistent().set(&key, &fee);
+}
+
+// Cumulative fee of one bucket, never reset
+pub fn read_bucket_lifetime_fee(e: &Env, bucket: FeeBucket) -> i128 {
+    let key = match bucket {
+        FeeBucket::NormalUnstake => StakingDataKey::LifetimeNormalUnstakeFee,
+        FeeBucket::InstantUnstake => StakingDataKey::LifetimeInstantUnstakeFee,
+        FeeBucket::LockPenalty => StakingDataKey::LifetimeLockPenalty,
+    };
+    e.storage().persistent().get(&key).unwrap_or(0)
+}
+
+pub fn write_bucket_lifetime_fee(e: &Env, bucket: FeeBucket, fee: i128) {
+    let key = match bucket {
+        FeeBucket::NormalUnstake => StakingDataKey::LifetimeNormalUnstakeFee,
+        FeeBucket::InstantUnstake => StakingDataKey::LifetimeInstantUnstakeFee,
+        FeeBucket::LockPenalty => StakingDataKey::LifetimeLockPenalty,
+    };
+    e.storage().persistent().set(&key, &fee);
+}
+
 pub fn read_pending_unstake_time(e: &Env, user: &Address, slot: u32) -> u64 {
     let key = PendingDataKey::PendingUnstakeTime(user.clone(), slot);
     e.storage().temporary().get(&key).unwrap_or(0)
@@ -235,3 +816,142 @@ pub fn write_pending_unstake_amount(e: &Env, user: &Address, slot: u32, amount:
     let key = PendingDataKey::PendingUnstakeAmount(user.clone(), slot);
     e.storage().temporary().set(&key, &amount);
 }
+
+// Claim ratio when the slot's amount was last booked. Requests made before
+// any loss was reported have none and start from the full ratio.
+pub fn read_pending_unstake_ratio(e: &Env, user: &Address, slot: u32) -> i128 {
+    let key = PendingDataKey::RequestClaimRatio(user.clone(), slot);
+    e.storage().temporary().get(&key).unwrap_or(RATE_PRECISION)
+}
+
+pub fn write_pending_unstake_ratio(e: &Env, user: &Address, slot: u32, ratio: i128) {
+    let key = PendingDataKey::RequestClaimRatio(user.clone(), slot);
+    e.storage().temporary().set(&key, &ratio);
+}
+
+pub fn read_keeper_tip(e: &Env, user: &Address) -> Option<i128> {
+    let key = UserDataKey::KeeperTip(user.clone());
+    let tip = e.storage().persistent().get(&key);
+    if tip.is_some() {
+        e.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+    tip
+}
+
+pub fn write_keeper_tip(e: &Env, user: &Address, tip: i128) {
+    let key = UserDataKey::KeeperTip(user.clone());
+    e.storage().persistent().set(&key, &tip);
+    e.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+pub fn remove_keeper_tip(e: &Env, user: &Address) {
+    let key = UserDataKey::KeeperTip(user.clone());
+    e.storage().persistent().remove(&key);
+}
+
+pub fn read_intent_signer(e: &Env, user: &Address) -> Option<BytesN<32>> {
+    let key = UserDataKey::IntentSigner(user.clone());
+    let public_key = e.storage().persistent().get(&key);
+    if public_key.is_some() {
+        e.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+    public_key
+}
+
+pub fn write_intent_signer(e: &Env, user: &Address, public_key: &BytesN<32>) {
+    let key = UserDataKey::IntentSigner(user.clone());
+    e.storage().persistent().set(&key, public_key);
+    e.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+// An archived nonce would let signed intents replay, so the entry is kept
+// alive on every read
+pub fn read_intent_nonce(e: &Env, user: &Address) -> u64 {
+    let key = UserDataKey::IntentNonce(user.clone());
+    let nonce = e.storage().persistent().get(&key);
+    if nonce.is_some() {
+        e.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+    nonce.unwrap_or(0)
+}
+
+pub fn write_intent_nonce(e: &Env, user: &Address, nonce: u64) {
+    let key = UserDataKey::IntentNonce(user.clone());
+    e.storage().persistent().set(&key, &nonce);
+    e.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+pub fn read_lock_position(e: &Env, user: &Address, id: u32) -> Option<LockPosition> {
+    let key = UserDataKey::LockPosition(user.clone(), id);
+    let position = e.storage().persistent().get(&key);
+    if position.is_some() {
+        e.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+    position
+}
+
+pub fn write_lock_position(e: &Env, user: &Address, position: &LockPosition) {
+    let key = UserDataKey::LockPosition(user.clone(), position.id);
+    e.storage().persistent().set(&key, position);
+    e.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+pub fn remove_lock_position(e: &Env, user: &Address, id: u32) {
+    let key = UserDataKey::LockPosition(user.clone(), id);
+    e.storage().persistent().remove(&key);
+}
+
+pub fn read_next_lock_id(e: &Env, user: &Address) -> u32 {
+    let key = UserDataKey::NextLockId(user.clone());
+    let id = e.storage().persistent().get(&key);
+    if id.is_some() {
+        e.storage().persistent().extend_ttl(
+            &key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+    id.unwrap_or(0)
+}
+
+pub fn write_next_lock_id(e: &Env, user: &Address, id: u32) {
+    let key = UserDataKey::NextLockId(user.clone());
+    e.storage().persistent().set(&key, &id);
+    e.storage().persistent().extend_ttl(
+        &key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
@@ -3,4 +3,12 @@ pub mod test_pumpbtc_staking;
 pub mod test_setup;
 pub mod test_initialize;
 pub mod test_admin_function;
-pub mod test_getter_function;
\ No newline at end of file
+pub mod test_getter_function;
+pub mod test_intent;
+pub mod test_events;
+pub mod test_decimals;
+pub mod test_sac;
+pub mod test_exchange_rate;
+pub mod test_rewards;
+pub mod test_lock;
+pub mod test_loss;
\ No newline at end of file
@@ -1,9 +1,12 @@
 use soroban_sdk::{
-    testutils::{Address as _, MockAuth, MockAuthInvoke},
+    testutils::{storage::Persistent as _, Address as _, Ledger, MockAuth, MockAuthInvoke},
     Address, IntoVal,
 };
 
-use crate::tests::test_setup::{PumpBTCStakingTest, pumpbtc_staking, STAKING_AMOUNT, DEPOSIT_AMOUNT, EXPIRATION_LEDGER};
+use crate::tests::test_setup::{
+    advance, pumpbtc_staking, PumpBTCStakingTest, DAY, DEPOSIT_AMOUNT, RATE_PRECISION,
+    STAKING_AMOUNT,
+};
 
 #[test]
 fn test_transfer_admin() {
@@ -27,10 +30,10 @@ fn test_renounce_admin() {
 #[test]
 fn test_pause_unpause() {
     let test = PumpBTCStakingTest::setup_initialized();
-    assert_eq!(false, test.contract.is_paused());
+    assert!(!test.contract.is_paused());
 
     test.contract.pause();
-    assert_eq!(true, test.contract.is_paused());
+    assert!(test.contract.is_paused());
 
     let result = test.contract.try_stake(&test.user1, &STAKING_AMOUNT);
     assert_eq!(
@@ -39,14 +42,7 @@ fn test_pause_unpause() {
     );
 
     test.contract.unpause();
-    assert_eq!(false, test.contract.is_paused());
-
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
+    assert!(!test.contract.is_paused());
 
     test.contract
         .mock_auths(&[MockAuth {
@@ -62,13 +58,12 @@ fn test_pause_unpause() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
-                            STAKING_AMOUNT.into_val(&test.env),        // amount
+                            STAKING_AMOUNT.into_val(&test.env), // amount
                         ],
                         sub_invokes: &[],
                     },
@@ -137,21 +132,15 @@ fn test_set_operator() {
 #[test]
 fn test_set_only_allow_stake() {
     let test = PumpBTCStakingTest::setup_initialized();
-    assert_eq!(true, test.contract.get_only_allow_stake());
+    assert!(test.contract.get_only_allow_stake());
 
     test.contract.set_only_allow_stake(&false);
-    assert_eq!(false, test.contract.get_only_allow_stake());
+    assert!(!test.contract.get_only_allow_stake());
 }
 
 #[test]
 fn test_deposit() {
     let test = PumpBTCStakingTest::setup_initialized();
-    test.asset_token.approve(
-        &test.operator,
-        &test.contract.address,
-        &DEPOSIT_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
     test.contract.deposit(&DEPOSIT_AMOUNT);
 
     assert_eq!(DEPOSIT_AMOUNT, test.contract.get_total_claimable_amount());
@@ -160,12 +149,6 @@ fn test_deposit() {
 #[test]
 fn test_withdraw() {
     let test = PumpBTCStakingTest::setup_initialized();
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
 
     test.contract
         .mock_auths(&[MockAuth {
@@ -181,13 +164,12 @@ fn test_withdraw() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
-                            STAKING_AMOUNT.into_val(&test.env),        // amount
+                            STAKING_AMOUNT.into_val(&test.env), // amount
                         ],
                         sub_invokes: &[],
                     },
@@ -217,26 +199,652 @@ fn test_withdraw() {
 fn test_withdraw_and_deposit() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.operator,
-        &test.contract.address,
-        &DEPOSIT_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
     test.contract.deposit(&DEPOSIT_AMOUNT);
 
     assert_eq!(DEPOSIT_AMOUNT, test.contract.get_total_claimable_amount());
 
-    test.asset_token.approve(
-        &test.operator,
-        &test.contract.address,
-        &DEPOSIT_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
     test.contract.withdraw_and_deposit(&DEPOSIT_AMOUNT);
 
     assert_eq!(
         DEPOSIT_AMOUNT + DEPOSIT_AMOUNT,
         test.contract.get_total_claimable_amount()
     );
-}
\ No newline at end of file
+
+    let result = test.contract.try_withdraw_and_deposit(&-1);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::NegativeAmountNotAllowed
+        ))
+    );
+}
+#[test]
+fn test_collect_fee_to_treasury() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let treasury = Address::generate(&test.env);
+
+    test.contract.set_treasury(&treasury);
+    assert_eq!(Some(treasury.clone()), test.contract.get_treasury());
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+
+    let fee = STAKING_AMOUNT * 500 / 10000;
+    test.contract.collect_fee();
+
+    assert_eq!(fee, test.asset_token.balance(&treasury));
+    assert_eq!(0i128, test.contract.get_collected_fee());
+}
+
+#[test]
+fn test_collect_fee_with_splits() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let recipient1 = Address::generate(&test.env);
+    let recipient2 = Address::generate(&test.env);
+
+    let fee_splits = soroban_sdk::vec![
+        &test.env,
+        pumpbtc_staking::FeeSplit {
+            recipient: recipient1.clone(),
+            bps: 3333,
+        },
+        pumpbtc_staking::FeeSplit {
+            recipient: recipient2.clone(),
+            bps: 6667,
+        },
+    ];
+    test.contract.set_fee_splits(&fee_splits);
+    assert_eq!(fee_splits, test.contract.get_fee_splits());
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+
+    let fee = STAKING_AMOUNT * 500 / 10000;
+    test.contract.collect_fee();
+
+    let payout1 = fee * 3333 / 10000;
+    assert_eq!(payout1, test.asset_token.balance(&recipient1));
+    assert_eq!(fee - payout1, test.asset_token.balance(&recipient2));
+}
+
+#[test]
+fn test_set_invalid_fee_splits() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    let fee_splits = soroban_sdk::vec![
+        &test.env,
+        pumpbtc_staking::FeeSplit {
+            recipient: test.admin.clone(),
+            bps: 5000,
+        },
+    ];
+    let result = test.contract.try_set_fee_splits(&fee_splits);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::InvalidConfig))
+    );
+}
+
+#[test]
+fn test_withdraw_keeps_liquidity_buffer() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract
+        .set_liquidity_buffer(&(STAKING_AMOUNT / 4), &0);
+    assert_eq!(
+        (STAKING_AMOUNT / 4, 0i128),
+        test.contract.get_liquidity_buffer()
+    );
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    assert_eq!(STAKING_AMOUNT / 4, test.contract.get_reserved_liquidity());
+    assert_eq!(
+        STAKING_AMOUNT - STAKING_AMOUNT / 4,
+        test.contract.get_withdrawable_amount()
+    );
+
+    let operator_balance = test.asset_token.balance(&test.operator);
+    test.contract.withdraw();
+
+    assert_eq!(
+        operator_balance + STAKING_AMOUNT - STAKING_AMOUNT / 4,
+        test.asset_token.balance(&test.operator)
+    );
+    assert_eq!(STAKING_AMOUNT / 4, test.contract.get_pending_stake_amount());
+
+    // Nothing left above the buffer
+    let result = test.contract.try_withdraw();
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::NoPendingStakeAmount
+        ))
+    );
+
+    // The buffer can still serve instant unstakes
+    test.contract.set_only_allow_stake(&false);
+    test.contract
+        .unstake_instant(&test.user1, &(STAKING_AMOUNT / 4));
+    assert_eq!(0i128, test.contract.get_pending_stake_amount());
+}
+
+#[test]
+fn test_liquidity_buffer_from_tvl() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    // 10% of the total staking amount
+    test.contract.set_liquidity_buffer(&0, &1000);
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    assert_eq!(STAKING_AMOUNT / 10, test.contract.get_reserved_liquidity());
+}
+
+#[test]
+fn test_withdraw_amount_to_destination() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let vault = Address::generate(&test.env);
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    let result = test.contract.try_withdraw_amount(&STAKING_AMOUNT, &vault);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::DestinationNotAllowed
+        ))
+    );
+
+    test.contract.set_withdraw_destination(&vault, &true);
+    assert!(test.contract.is_withdraw_destination(&vault));
+
+    // The allowlist lives outside the instance entry
+    test.env.as_contract(&test.contract.address, || {
+        let key = crate::storage::UserDataKey::WithdrawDestination(vault.clone());
+        assert!(test.env.storage().persistent().has(&key));
+        assert!(
+            test.env.storage().persistent().get_ttl(&key)
+                >= crate::storage::PERSISTENT_LIFETIME_THRESHOLD
+        );
+    });
+
+    test.contract.withdraw_amount(&(STAKING_AMOUNT / 2), &vault);
+    assert_eq!(STAKING_AMOUNT / 2, test.asset_token.balance(&vault));
+    assert_eq!(STAKING_AMOUNT / 2, test.contract.get_pending_stake_amount());
+
+    let result = test.contract.try_withdraw_amount(&STAKING_AMOUNT, &vault);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientPendingStakeAmount
+        ))
+    );
+
+    test.contract.set_withdraw_destination(&vault, &false);
+    assert!(!test.contract.is_withdraw_destination(&vault));
+}
+
+#[test]
+fn test_withdraw_limit_pauses_operator() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let vault = Address::generate(&test.env);
+
+    test.contract
+        .set_withdraw_limit(&(STAKING_AMOUNT / 2), &86400);
+    test.contract.set_withdraw_destination(&vault, &true);
+    assert_eq!(
+        STAKING_AMOUNT / 2,
+        test.contract.get_remaining_withdraw_allowance()
+    );
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    assert_eq!(
+        STAKING_AMOUNT / 4,
+        test.contract.withdraw_amount(&(STAKING_AMOUNT / 4), &vault)
+    );
+    assert_eq!(
+        STAKING_AMOUNT / 4,
+        test.contract.get_remaining_withdraw_allowance()
+    );
+
+    // Sweeping the rest breaches the limit: nothing moves and operator flows pause
+    assert_eq!(0i128, test.contract.withdraw());
+    assert!(test.contract.is_operator_paused());
+    assert_eq!(
+        STAKING_AMOUNT - STAKING_AMOUNT / 4,
+        test.contract.get_pending_stake_amount()
+    );
+
+    let result = test.contract.try_withdraw();
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::OperatorFlowsPaused
+        ))
+    );
+
+    // The allowance comes back once the first outflow's bucket has left the
+    // window and the admin resumes operator flows
+    advance(&test, DAY + DAY / 24);
+    test.contract.unpause_operator();
+    test.contract.set_withdraw_limit(&STAKING_AMOUNT, &86400);
+    assert_eq!(
+        STAKING_AMOUNT - STAKING_AMOUNT / 4,
+        test.contract.withdraw()
+    );
+    assert_eq!(0i128, test.contract.get_pending_stake_amount());
+    assert_eq!(
+        STAKING_AMOUNT / 4,
+        test.contract.get_remaining_withdraw_allowance()
+    );
+}
+
+#[test]
+fn test_withdraw_limit_is_rolling() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.set_withdraw_limit(&STAKING_AMOUNT, &DAY);
+    test.contract.stake(&test.user1, &(STAKING_AMOUNT * 3));
+    let vault = Address::generate(&test.env);
+    test.contract.set_withdraw_destination(&vault, &true);
+
+    // A small outflow early in the day, then the rest of the limit just
+    // before a day has passed since it
+    test.contract
+        .withdraw_amount(&(STAKING_AMOUNT / 10), &vault);
+    advance(&test, DAY - 60);
+    test.contract
+        .withdraw_amount(&(STAKING_AMOUNT - STAKING_AMOUNT / 10), &vault);
+    assert_eq!(0, test.contract.get_remaining_withdraw_allowance());
+
+    // Right after the day boundary only the early outflow has left the
+    // window, so taking the full limit again trips the circuit breaker
+    advance(&test, 2 * DAY / 24);
+    assert_eq!(
+        STAKING_AMOUNT / 10,
+        test.contract.get_remaining_withdraw_allowance()
+    );
+    assert_eq!(0, test.contract.withdraw_amount(&STAKING_AMOUNT, &vault));
+    assert!(test.contract.is_operator_paused());
+    assert_eq!(STAKING_AMOUNT * 2, test.contract.get_pending_stake_amount());
+
+    // A full window after the last outflow the whole limit is available again
+    test.contract.unpause_operator();
+    advance(&test, DAY);
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_remaining_withdraw_allowance()
+    );
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.withdraw_amount(&STAKING_AMOUNT, &vault)
+    );
+}
+
+#[test]
+fn test_withdraw_limit_keeps_deposit_leg() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract
+        .set_withdraw_limit(&(STAKING_AMOUNT / 2), &86400);
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    // The net outflow breaches the limit: the withdraw leg is skipped but the
+    // deposit is still taken and booked
+    let operator_balance = test.asset_token.balance(&test.operator);
+    assert_eq!(
+        0i128,
+        test.contract.withdraw_and_deposit(&(STAKING_AMOUNT / 4))
+    );
+    assert!(test.contract.is_operator_paused());
+    assert_eq!(STAKING_AMOUNT, test.contract.get_pending_stake_amount());
+    assert_eq!(
+        STAKING_AMOUNT / 4,
+        test.contract.get_total_claimable_amount()
+    );
+    assert_eq!(
+        operator_balance - STAKING_AMOUNT / 4,
+        test.asset_token.balance(&test.operator)
+    );
+
+    // Within the limit both legs run and only the net amount moves
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 86400;
+    });
+    test.contract.unpause_operator();
+    let operator_balance = test.asset_token.balance(&test.operator);
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract
+            .withdraw_and_deposit(&(STAKING_AMOUNT * 3 / 4))
+    );
+    assert!(!test.contract.is_operator_paused());
+    assert_eq!(0i128, test.contract.get_pending_stake_amount());
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_claimable_amount());
+    assert_eq!(
+        operator_balance + STAKING_AMOUNT / 4,
+        test.asset_token.balance(&test.operator)
+    );
+}
+
+#[test]
+fn test_check_solvency_and_reconcile() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    let report = test.contract.check_solvency();
+    assert_eq!(STAKING_AMOUNT, report.balance);
+    assert_eq!(STAKING_AMOUNT, report.liabilities);
+    assert_eq!(0i128, report.surplus);
+
+    let result = test.contract.try_reconcile();
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientSurplus
+        ))
+    );
+
+    // Tokens sent to the contract by mistake show up as surplus
+    let stray_amount = 12_345i128;
+    test.asset_token
+        .transfer(&test.user2, &test.contract.address, &stray_amount);
+    assert_eq!(stray_amount, test.contract.check_solvency().surplus);
+
+    assert_eq!(stray_amount, test.contract.reconcile());
+    assert_eq!(stray_amount, test.contract.get_unallocated_amount());
+
+    let report = test.contract.check_solvency();
+    assert_eq!(stray_amount, report.unallocated_amount);
+    assert_eq!(0i128, report.surplus);
+}
+
+#[test]
+fn test_rescue_tokens() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let recipient = Address::generate(&test.env);
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    // Staked assets are liabilities and cannot be rescued
+    let result = test
+        .contract
+        .try_rescue_tokens(&test.asset_token.address, &recipient, &1);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientSurplus
+        ))
+    );
+
+    let stray_amount = 12_345i128;
+    test.asset_token
+        .transfer(&test.user2, &test.contract.address, &stray_amount);
+    test.contract.reconcile();
+    test.asset_token
+        .transfer(&test.user2, &test.contract.address, &stray_amount);
+
+    let result = test.contract.try_rescue_tokens(
+        &test.asset_token.address,
+        &recipient,
+        &(stray_amount * 2 + 1),
+    );
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientSurplus
+        ))
+    );
+
+    test.contract
+        .rescue_tokens(&test.asset_token.address, &recipient, &(stray_amount * 2));
+    assert_eq!(stray_amount * 2, test.asset_token.balance(&recipient));
+    assert_eq!(0i128, test.contract.get_unallocated_amount());
+    assert_eq!(STAKING_AMOUNT, test.contract.check_solvency().balance);
+
+    // Any other token can be moved in full
+    test.pump_token
+        .transfer(&test.user1, &test.contract.address, &STAKING_AMOUNT);
+    test.contract
+        .rescue_tokens(&test.pump_token.address, &recipient, &STAKING_AMOUNT);
+    assert_eq!(STAKING_AMOUNT, test.pump_token.balance(&recipient));
+}
+
+#[test]
+fn test_migrate_schema_version() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    assert_eq!(2u32, test.contract.version());
+
+    // Simulate a deployment from before the schema version was recorded
+    test.env.as_contract(&test.contract.address, || {
+        test.env
+            .storage()
+            .instance()
+            .remove(&crate::storage::DataKey::SchemaVersion);
+    });
+    assert_eq!(0u32, test.contract.version());
+
+    let result = test.contract.try_stake(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::SchemaVersionMismatch
+        ))
+    );
+    let result = test.contract.try_pause();
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::SchemaVersionMismatch
+        ))
+    );
+
+    let result = test.contract.try_migrate(&1);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::SchemaVersionMismatch
+        ))
+    );
+
+    assert_eq!(2u32, test.contract.migrate(&0));
+    assert_eq!(2u32, test.contract.version());
+
+    // Running it again on an up-to-date schema is a no-op
+    assert_eq!(2u32, test.contract.migrate(&2));
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_staking_amount());
+}
+
+#[test]
+fn test_migrate_from_first_schema_version() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &(STAKING_AMOUNT * 2));
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract
+        .unstake_instant(&test.user1, &(STAKING_AMOUNT / 2));
+    let instant_fee = STAKING_AMOUNT / 2 * 500 / 10000;
+
+    // Simulate a version 1 deployment holding that state, written before
+    // exchange rates, claim ratios, pump token kinds and daily obligations
+    let timestamp = test.env.ledger().timestamp();
+    let slot = crate::utils::get_date_slot(timestamp);
+    let maturity_day = crate::utils::get_maturity_day(timestamp);
+    test.env.as_contract(&test.contract.address, || {
+        let storage = test.env.storage();
+        storage
+            .instance()
+            .set(&crate::storage::DataKey::SchemaVersion, &1u32);
+        for key in [
+            crate::storage::DataKey::ExchangeRate,
+            crate::storage::DataKey::ClaimRatio,
+            crate::storage::DataKey::PumpTokenKind,
+            crate::storage::DataKey::PumpTokenDecimal,
+        ] {
+            storage.instance().remove(&key);
+        }
+        storage
+            .temporary()
+            .remove(&crate::storage::PendingDataKey::RequestClaimRatio(
+                test.user1.clone(),
+                slot,
+            ));
+        storage
+            .persistent()
+            .remove(&crate::storage::StakingDataKey::DailyRequestedAmount(
+                maturity_day,
+            ));
+        storage
+            .persistent()
+            .remove(&crate::storage::StakingDataKey::DailyClaimRatio(
+                maturity_day,
+            ));
+    });
+
+    let result = test.contract.try_claim_all(&test.user1);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::SchemaVersionMismatch
+        ))
+    );
+
+    assert_eq!(2u32, test.contract.migrate(&1));
+    assert_eq!(2u32, test.contract.version());
+
+    assert_eq!(RATE_PRECISION, test.contract.get_exchange_rate());
+    assert_eq!(RATE_PRECISION, test.contract.get_claim_ratio());
+    assert_eq!(
+        pumpbtc_staking::PumpTokenKind::Minter,
+        test.contract.get_pump_token_kind()
+    );
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_pending_unstake_amount(&test.user1, &slot)
+    );
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_requested_amount());
+    assert_eq!(STAKING_AMOUNT / 2, test.contract.get_total_staking_amount());
+    assert_eq!(instant_fee, test.contract.get_collected_instant_fee());
+    assert_eq!(instant_fee, test.contract.get_collected_fee());
+
+    // The old request is not in the daily schedule but still counts as due
+    advance(&test, 9 * DAY);
+    assert_eq!(STAKING_AMOUNT, test.contract.get_funding_shortfall());
+
+    test.contract.deposit(&STAKING_AMOUNT);
+    assert_eq!(0i128, test.contract.get_funding_shortfall());
+
+    let balance = test.asset_token.balance(&test.user1);
+    test.contract.claim_all(&test.user1);
+    let normal_fee = STAKING_AMOUNT * 100 / 10000;
+    assert_eq!(
+        balance + STAKING_AMOUNT - normal_fee,
+        test.asset_token.balance(&test.user1)
+    );
+    assert_eq!(0i128, test.contract.get_total_requested_amount());
+    assert_eq!(instant_fee + normal_fee, test.contract.get_collected_fee());
+    assert!(!test.contract.is_paused());
+}
+
+#[test]
+fn test_announce_and_execute_upgrade() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let hash = test
+        .env
+        .deployer()
+        .upload_contract_wasm(pumpbtc_staking::WASM);
+
+    let result = test.contract.try_execute_upgrade();
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::NoPendingUpgrade))
+    );
+
+    test.contract.announce_upgrade(&hash);
+    let pending_upgrade = test.contract.get_pending_upgrade().unwrap();
+    assert_eq!(hash, pending_upgrade.hash);
+    assert_eq!(
+        test.env.ledger().timestamp() + 3 * 24 * 60 * 60,
+        pending_upgrade.executable_after
+    );
+
+    let result = test.contract.try_execute_upgrade();
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::UpgradeNotReady))
+    );
+
+    test.contract.cancel_upgrade();
+    assert_eq!(None, test.contract.get_pending_upgrade());
+
+    test.contract.announce_upgrade(&hash);
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 3 * 24 * 60 * 60; // 3 days
+    });
+    test.contract.execute_upgrade();
+    assert_eq!(None, test.contract.get_pending_upgrade());
+    assert_eq!(2u32, test.contract.version());
+}
+
+#[test]
+fn test_funding_schedule_and_shortfall() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let day = 24 * 60 * 60;
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp = 1_700_000_000;
+    });
+
+    for user in [&test.user1, &test.user2] {
+        test.contract.stake(user, &STAKING_AMOUNT);
+    }
+    test.contract.set_only_allow_stake(&false);
+
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += day;
+    });
+    test.contract
+        .unstake_request(&test.user2, &(STAKING_AMOUNT / 2));
+
+    // Requests mature nine days out, one day apart
+    let schedule = test.contract.get_funding_schedule(&20);
+    assert_eq!(10, schedule.len());
+    assert_eq!(STAKING_AMOUNT, schedule.get(8).unwrap().outstanding_amount);
+    assert_eq!(
+        STAKING_AMOUNT / 2,
+        schedule.get(9).unwrap().outstanding_amount
+    );
+    assert_eq!(0i128, schedule.get(0).unwrap().requested_amount);
+    assert_eq!(0i128, test.contract.get_funding_shortfall());
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 8 * day;
+    });
+    let today = test.contract.get_funding_schedule(&1).get(0).unwrap();
+    assert_eq!(STAKING_AMOUNT, today.outstanding_amount);
+    assert!(today.start_time <= test.env.ledger().timestamp());
+    assert!(test.env.ledger().timestamp() < today.start_time + day);
+    assert_eq!(STAKING_AMOUNT, test.contract.get_funding_shortfall());
+
+    test.contract.deposit(&STAKING_AMOUNT);
+    assert_eq!(0i128, test.contract.get_funding_shortfall());
+
+    test.contract.claim_all(&test.user1);
+    let today = test.contract.get_funding_schedule(&1).get(0).unwrap();
+    assert_eq!(STAKING_AMOUNT, today.claimed_amount);
+    assert_eq!(0i128, today.outstanding_amount);
+
+    // The next day user2's request falls due
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += day;
+    });
+    assert_eq!(STAKING_AMOUNT / 2, test.contract.get_funding_shortfall());
+}
@@ -0,0 +1,192 @@
+use soroban_sdk::testutils::Ledger;
+
+use crate::tests::test_events::last_event;
+use crate::tests::test_setup::{pumpbtc_staking, PumpBTCStakingTest, STAKING_AMOUNT};
+
+const ASSET_DECIMALS: [u32; 5] = [6, 7, 8, 12, 18];
+
+// pumpBTC amount in asset units, rounded down
+fn to_asset(asset_decimal: u32, amount: i128) -> i128 {
+    if asset_decimal >= 8 {
+        amount * 10i128.pow(asset_decimal - 8)
+    } else {
+        amount / 10i128.pow(8 - asset_decimal)
+    }
+}
+
+fn stake(test: &PumpBTCStakingTest, amount: i128) {
+    test.contract.stake(&test.user1, &amount);
+}
+
+#[test]
+fn test_stake_with_asset_decimals() {
+    for asset_decimal in ASSET_DECIMALS {
+        let test = PumpBTCStakingTest::setup_initialized_with_asset_decimal(asset_decimal);
+        assert_eq!(asset_decimal, test.contract.get_asset_decimal());
+
+        let balance = test.asset_token.balance(&test.user1);
+        stake(&test, STAKING_AMOUNT);
+
+        assert_eq!(
+            balance - to_asset(asset_decimal, STAKING_AMOUNT),
+            test.asset_token.balance(&test.user1)
+        );
+        assert_eq!(
+            to_asset(asset_decimal, STAKING_AMOUNT),
+            test.asset_token.balance(&test.contract.address)
+        );
+        assert_eq!(STAKING_AMOUNT, test.contract.get_total_staking_amount());
+    }
+}
+
+#[test]
+fn test_reject_dust_with_fewer_decimals() {
+    for asset_decimal in [6, 7] {
+        let test = PumpBTCStakingTest::setup_initialized_with_asset_decimal(asset_decimal);
+
+        // One satoshi is below the smallest unit of the asset
+        let result = test.contract.try_stake(&test.user1, &(STAKING_AMOUNT + 1));
+        assert_eq!(
+            result,
+            Err(Ok(
+                pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+            ))
+        );
+
+        let result = test.contract.try_deposit(&(STAKING_AMOUNT + 1));
+        assert_eq!(
+            result,
+            Err(Ok(
+                pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+            ))
+        );
+    }
+}
+
+#[test]
+fn test_withdraw_and_deposit_rejects_dust() {
+    for asset_decimal in [6, 7] {
+        let test = PumpBTCStakingTest::setup_initialized_with_asset_decimal(asset_decimal);
+        stake(&test, STAKING_AMOUNT);
+
+        // The deposit leg is booked exactly, like `deposit`
+        let result = test
+            .contract
+            .try_withdraw_and_deposit(&(STAKING_AMOUNT * 2 + 1));
+        assert_eq!(
+            result,
+            Err(Ok(
+                pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+            ))
+        );
+
+        // Only the net deposit is transferred
+        let balance = test.asset_token.balance(&test.operator);
+        test.contract.withdraw_and_deposit(&(STAKING_AMOUNT * 2));
+        let event: pumpbtc_staking::DepositEvent = last_event(&test, "deposit");
+        assert_eq!(STAKING_AMOUNT * 2, event.amount);
+        assert_eq!(
+            to_asset(asset_decimal, STAKING_AMOUNT * 2),
+            event.asset_amount
+        );
+        assert_eq!(
+            balance - to_asset(asset_decimal, STAKING_AMOUNT),
+            test.asset_token.balance(&test.operator)
+        );
+        assert_eq!(
+            STAKING_AMOUNT * 2,
+            test.contract.get_total_claimable_amount()
+        );
+        assert!(!test.contract.is_paused());
+    }
+}
+
+#[test]
+fn test_payouts_round_down_with_asset_decimals() {
+    for asset_decimal in ASSET_DECIMALS {
+        let test = PumpBTCStakingTest::setup_initialized_with_asset_decimal(asset_decimal);
+        stake(&test, STAKING_AMOUNT * 3);
+        test.contract.set_only_allow_stake(&false);
+
+        // 5% of a third of a BTC leaves a payout the asset cannot represent
+        let amount = STAKING_AMOUNT / 3;
+        let payout = amount - amount * 500 / 10000;
+        let balance = test.asset_token.balance(&test.user1);
+        test.contract.unstake_instant(&test.user1, &amount);
+        assert_eq!(
+            balance + to_asset(asset_decimal, payout),
+            test.asset_token.balance(&test.user1)
+        );
+
+        test.contract.unstake_request(&test.user1, &amount);
+        test.contract.deposit(&STAKING_AMOUNT);
+
+        test.env.ledger().with_mut(|ledger| {
+            ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
+        });
+        let payout = amount - amount * 100 / 10000;
+        let balance = test.asset_token.balance(&test.user1);
+        test.contract.claim_all(&test.user1);
+        assert_eq!(
+            balance + to_asset(asset_decimal, payout),
+            test.asset_token.balance(&test.user1)
+        );
+
+        // Rounding dust stays in the contract
+        assert!(!test.contract.is_paused());
+        assert!(test.contract.check_solvency().surplus >= 0);
+    }
+}
+
+#[test]
+fn test_stake_and_unstake_native() {
+    for asset_decimal in ASSET_DECIMALS {
+        let test = PumpBTCStakingTest::setup_initialized_with_asset_decimal(asset_decimal);
+        let asset_amount = to_asset(asset_decimal, STAKING_AMOUNT * 2);
+
+        let pump_balance = test.pump_token.balance(&test.user1);
+        assert_eq!(
+            STAKING_AMOUNT * 2,
+            test.contract.stake_native(&test.user1, &asset_amount)
+        );
+        assert_eq!(
+            pump_balance + STAKING_AMOUNT * 2,
+            test.pump_token.balance(&test.user1)
+        );
+
+        test.contract.set_only_allow_stake(&false);
+        let asset_amount = to_asset(asset_decimal, STAKING_AMOUNT);
+        assert_eq!(
+            STAKING_AMOUNT,
+            test.contract
+                .unstake_instant_native(&test.user1, &asset_amount)
+        );
+        assert_eq!(
+            pump_balance + STAKING_AMOUNT,
+            test.pump_token.balance(&test.user1)
+        );
+        assert_eq!(STAKING_AMOUNT, test.contract.get_total_staking_amount());
+
+        // Below one satoshi the amount cannot be converted
+        if asset_decimal > 8 {
+            let result = test
+                .contract
+                .try_stake_native(&test.user1, &(asset_amount + 1));
+            assert_eq!(
+                result,
+                Err(Ok(
+                    pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+                ))
+            );
+            let result = test
+                .contract
+                .try_unstake_instant_native(&test.user1, &(asset_amount - 1));
+            assert_eq!(
+                result,
+                Err(Ok(
+                    pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+                ))
+            );
+        }
+    }
+}
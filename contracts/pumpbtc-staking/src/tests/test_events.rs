@@ -0,0 +1,664 @@
+extern crate std;
+
+use soroban_sdk::{
+    testutils::{Address as _, Events, Ledger},
+    Address, Env, String, Symbol, TryFromVal, TryIntoVal, Val,
+};
+
+use crate::tests::test_setup::{
+    pumpbtc_staking, PumpBTCStakingTest, DEPOSIT_AMOUNT, STAKING_AMOUNT,
+};
+
+const EVENT_VERSION: u32 = 3;
+
+// Payloads of the staking contract's `name` events from the last invocation,
+// checking the ("PumpBTCStaking", name, version) topics of each
+pub fn contract_events<T>(test: &PumpBTCStakingTest, name: &str) -> std::vec::Vec<T>
+where
+    T: TryFromVal<Env, Val>,
+{
+    let mut events = std::vec::Vec::new();
+    for (contract, topics, data) in test.env.events().all().iter() {
+        if contract != test.contract.address {
+            continue;
+        }
+        let topic: Symbol = topics.get(1).unwrap().try_into_val(&test.env).unwrap();
+        if topic != Symbol::new(&test.env, name) {
+            continue;
+        }
+
+        assert_eq!(3, topics.len());
+        let contract_topic: String = topics.get(0).unwrap().try_into_val(&test.env).unwrap();
+        assert_eq!(
+            String::from_str(&test.env, "PumpBTCStaking"),
+            contract_topic
+        );
+        let version: u32 = topics.get(2).unwrap().try_into_val(&test.env).unwrap();
+        assert_eq!(EVENT_VERSION, version);
+
+        events.push(T::try_from_val(&test.env, &data).unwrap());
+    }
+    events
+}
+
+pub fn last_event<T>(test: &PumpBTCStakingTest, name: &str) -> T
+where
+    T: TryFromVal<Env, Val>,
+{
+    contract_events(test, name)
+        .pop()
+        .unwrap_or_else(|| panic!("no {} event", name))
+}
+
+fn stake(test: &PumpBTCStakingTest, user: &Address, amount: i128) {
+    test.contract.stake(user, &amount);
+}
+
+#[test]
+fn test_config_events() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let treasury = Address::generate(&test.env);
+
+    test.contract.set_stake_asset_cap(&(STAKING_AMOUNT * 1000));
+    assert_eq!(
+        pumpbtc_staking::NewTotalStakingCapEvent {
+            old_total_staking_cap: 10_000_000_000,
+            new_total_staking_cap: STAKING_AMOUNT * 1000,
+        },
+        last_event(&test, "set_cap")
+    );
+
+    test.contract.set_normal_unstake_fee(&200);
+    assert_eq!(
+        pumpbtc_staking::NewNormalUnstakeFeeEvent {
+            old_normal_unstake_fee: 100,
+            new_normal_unstake_fee: 200,
+        },
+        last_event(&test, "set_nfee")
+    );
+
+    test.contract.set_instant_unstake_fee(&600);
+    assert_eq!(
+        pumpbtc_staking::SetInstantUnstakeFeeEvent {
+            old_instant_unstake_fee: 500,
+            new_instant_unstake_fee: 600,
+        },
+        last_event(&test, "set_ifee")
+    );
+
+    test.contract.set_instant_fee_curve(&100, &1000);
+    assert_eq!(
+        pumpbtc_staking::SetInstantFeeCurveEvent {
+            enabled: true,
+            base_fee: 100,
+            max_fee: 1000,
+        },
+        last_event(&test, "set_curve")
+    );
+    test.contract.remove_instant_fee_curve();
+    assert!(!last_event::<pumpbtc_staking::SetInstantFeeCurveEvent>(&test, "set_curve").enabled);
+
+    test.contract.set_liquidity_buffer(&STAKING_AMOUNT, &500);
+    assert_eq!(
+        pumpbtc_staking::SetLiquidityBufferEvent {
+            buffer_amount: STAKING_AMOUNT,
+            buffer_bps: 500,
+        },
+        last_event(&test, "set_buf")
+    );
+
+    let new_operator = Address::generate(&test.env);
+    test.contract.set_operator(&new_operator);
+    assert_eq!(
+        pumpbtc_staking::SetOperatorEvent {
+            old_operator: Some(test.operator.clone()),
+            new_operator,
+        },
+        last_event(&test, "set_op")
+    );
+
+    test.contract.set_only_allow_stake(&false);
+    assert_eq!(
+        pumpbtc_staking::SetOnlyAllowStakeEvent {
+            only_allow_stake: false,
+        },
+        last_event(&test, "set_allow")
+    );
+
+    test.contract.set_treasury(&treasury);
+    assert_eq!(
+        pumpbtc_staking::SetTreasuryEvent {
+            old_treasury: None,
+            new_treasury: treasury.clone(),
+        },
+        last_event(&test, "set_trsy")
+    );
+
+    let fee_splits = soroban_sdk::vec![
+        &test.env,
+        pumpbtc_staking::FeeSplit {
+            recipient: treasury.clone(),
+            bps: 10000,
+        },
+    ];
+    test.contract.set_fee_splits(&fee_splits);
+    assert_eq!(
+        pumpbtc_staking::SetFeeSplitsEvent { fee_splits },
+        last_event(&test, "set_split")
+    );
+
+    test.contract.set_withdraw_destination(&treasury, &true);
+    assert_eq!(
+        pumpbtc_staking::SetWithdrawDestinationEvent {
+            destination: treasury.clone(),
+            allowed: true,
+        },
+        last_event(&test, "set_dest")
+    );
+
+    test.contract.set_withdraw_limit(&STAKING_AMOUNT, &3600);
+    assert_eq!(
+        pumpbtc_staking::SetWithdrawLimitEvent {
+            withdraw_limit: STAKING_AMOUNT,
+            withdraw_window: 3600,
+        },
+        last_event(&test, "set_limit")
+    );
+
+    test.contract.set_keeper_tip(&test.user1, &true, &50);
+    assert_eq!(
+        pumpbtc_staking::SetKeeperTipEvent {
+            user: test.user1.clone(),
+            opted_in: true,
+            keeper_tip: 50,
+        },
+        last_event(&test, "set_tip")
+    );
+
+    let public_key = soroban_sdk::BytesN::from_array(&test.env, &[1u8; 32]);
+    test.contract.set_intent_signer(&test.user1, &public_key);
+    assert_eq!(
+        pumpbtc_staking::SetIntentSignerEvent {
+            user: test.user1.clone(),
+            public_key,
+        },
+        last_event(&test, "set_sign")
+    );
+
+    let oracle = Address::generate(&test.env);
+    test.contract.set_rate_oracle(&oracle);
+    assert_eq!(
+        pumpbtc_staking::SetRateOracleEvent {
+            old_oracle: None,
+            new_oracle: oracle,
+        },
+        last_event(&test, "set_oracl")
+    );
+
+    test.contract.set_exchange_rate_bounds(&500, &3600);
+    assert_eq!(
+        pumpbtc_staking::SetRateBoundsEvent {
+            max_change_bps: 500,
+            min_update_interval: 3600,
+        },
+        last_event(&test, "set_rbnds")
+    );
+
+    let old_lock_penalty = test.contract.get_lock_penalty();
+    test.contract.set_lock_penalty(&1000);
+    assert_eq!(
+        pumpbtc_staking::SetLockPenaltyEvent {
+            old_lock_penalty,
+            new_lock_penalty: 1000,
+        },
+        last_event(&test, "set_lpen")
+    );
+}
+
+#[test]
+fn test_user_flow_events() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp = 1_700_000_000;
+    });
+    let start_time = test.env.ledger().timestamp();
+
+    stake(&test, &test.user1, STAKING_AMOUNT * 3);
+    assert_eq!(
+        pumpbtc_staking::StakeEvent {
+            user: test.user1.clone(),
+            amount: STAKING_AMOUNT * 3,
+            pump_amount: STAKING_AMOUNT * 3,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT * 3,
+            timestamp: start_time,
+            total_staking_amount: STAKING_AMOUNT * 3,
+            pending_stake_amount: STAKING_AMOUNT * 3,
+        },
+        last_event(&test, "stake")
+    );
+
+    test.contract.set_only_allow_stake(&false);
+
+    let instant_fee = STAKING_AMOUNT * 500 / 10000;
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        pumpbtc_staking::UnstakeInstantEvent {
+            user: test.user1.clone(),
+            amount: STAKING_AMOUNT,
+            pump_amount: STAKING_AMOUNT,
+            fee: instant_fee,
+            net_amount: STAKING_AMOUNT - instant_fee,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT - instant_fee,
+            timestamp: start_time,
+            total_staking_amount: STAKING_AMOUNT * 2,
+            pending_stake_amount: STAKING_AMOUNT * 2,
+        },
+        last_event(&test, "unstake_i")
+    );
+    assert_eq!(
+        pumpbtc_staking::AccrueFeeEvent {
+            bucket: pumpbtc_staking::FeeBucket::InstantUnstake,
+            fee_amount: instant_fee,
+        },
+        last_event(&test, "fee")
+    );
+
+    let slot = test.contract.get_max_date_slot();
+    let slot = ((start_time + 8 * 3600) / 86400 % slot as u64) as u32;
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        pumpbtc_staking::UnstakeRequestEvent {
+            user: test.user1.clone(),
+            amount: STAKING_AMOUNT,
+            pump_amount: STAKING_AMOUNT,
+            slot,
+            request_time: start_time,
+            claimable_time: start_time + 9 * 86400,
+            total_staking_amount: STAKING_AMOUNT,
+            total_requested_amount: STAKING_AMOUNT,
+        },
+        last_event(&test, "unstake")
+    );
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 86400;
+    });
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+
+    test.contract.deposit(&DEPOSIT_AMOUNT);
+
+    let normal_fee = STAKING_AMOUNT * 100 / 10000;
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 8 * 86400;
+    });
+    test.contract.claim_slot(&test.user1, &slot);
+    assert_eq!(
+        pumpbtc_staking::ClaimSlotEvent {
+            user: test.user1.clone(),
+            slot,
+            amount: STAKING_AMOUNT,
+            fee: normal_fee,
+            net_amount: STAKING_AMOUNT - normal_fee,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT - normal_fee,
+            timestamp: start_time + 9 * 86400,
+            total_requested_amount: STAKING_AMOUNT,
+            total_claimable_amount: DEPOSIT_AMOUNT - STAKING_AMOUNT,
+        },
+        last_event(&test, "claim")
+    );
+    assert_eq!(
+        pumpbtc_staking::AccrueFeeEvent {
+            bucket: pumpbtc_staking::FeeBucket::NormalUnstake,
+            fee_amount: normal_fee,
+        },
+        last_event(&test, "fee")
+    );
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 86400;
+    });
+    test.contract.claim_all(&test.user1);
+    assert_eq!(
+        pumpbtc_staking::ClaimAllEvent {
+            user: test.user1.clone(),
+            amount: STAKING_AMOUNT,
+            fee: normal_fee,
+            net_amount: STAKING_AMOUNT - normal_fee,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT - normal_fee,
+            timestamp: start_time + 10 * 86400,
+            total_requested_amount: 0,
+            total_claimable_amount: DEPOSIT_AMOUNT - STAKING_AMOUNT * 2,
+        },
+        last_event(&test, "claim_all")
+    );
+
+    test.contract.collect_fee();
+    assert_eq!(
+        pumpbtc_staking::CollectFeeEvent {
+            recipient: test.admin.clone(),
+            fee_amount: instant_fee + normal_fee * 2,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: instant_fee + normal_fee * 2,
+            timestamp: start_time + 10 * 86400,
+        },
+        last_event(&test, "collect")
+    );
+}
+
+#[test]
+fn test_claim_for_event() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let keeper = Address::generate(&test.env);
+
+    stake(&test, &test.user1, STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_keeper_tip(&test.user1, &true, &100);
+
+    test.contract.deposit(&STAKING_AMOUNT);
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 9 * 86400;
+    });
+    let users = soroban_sdk::vec![&test.env, test.user1.clone(), test.user2.clone()];
+    test.contract.claim_for(&keeper, &users);
+
+    let fee = STAKING_AMOUNT * 100 / 10000;
+    let events: std::vec::Vec<pumpbtc_staking::ClaimForEvent> = contract_events(&test, "claim_for");
+    assert_eq!(
+        std::vec![
+            pumpbtc_staking::ClaimForEvent {
+                keeper: keeper.clone(),
+                user: test.user1.clone(),
+                claimed: true,
+                amount: STAKING_AMOUNT,
+                fee,
+                keeper_tip: (STAKING_AMOUNT - fee) * 100 / 10000,
+            },
+            pumpbtc_staking::ClaimForEvent {
+                keeper,
+                user: test.user2.clone(),
+                claimed: false,
+                amount: 0,
+                fee: 0,
+                keeper_tip: 0,
+            },
+        ],
+        events
+    );
+}
+
+#[test]
+fn test_claim_for_skips_unfunded_user() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let keeper = Address::generate(&test.env);
+
+    for user in [&test.user1, &test.user2] {
+        stake(&test, user, STAKING_AMOUNT);
+    }
+    test.contract.set_only_allow_stake(&false);
+    for user in [&test.user1, &test.user2] {
+        test.contract.unstake_request(user, &STAKING_AMOUNT);
+        test.contract.set_keeper_tip(user, &true, &0);
+    }
+
+    // Only one of the two claims is funded
+    test.contract.deposit(&STAKING_AMOUNT);
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 9 * 86400;
+    });
+    let users = soroban_sdk::vec![&test.env, test.user1.clone(), test.user2.clone()];
+    assert_eq!(1u32, test.contract.claim_for(&keeper, &users));
+
+    let fee = STAKING_AMOUNT * 100 / 10000;
+    let events: std::vec::Vec<pumpbtc_staking::ClaimForEvent> = contract_events(&test, "claim_for");
+    assert_eq!(
+        std::vec![
+            pumpbtc_staking::ClaimForEvent {
+                keeper: keeper.clone(),
+                user: test.user1.clone(),
+                claimed: true,
+                amount: STAKING_AMOUNT,
+                fee,
+                keeper_tip: 0,
+            },
+            pumpbtc_staking::ClaimForEvent {
+                keeper,
+                user: test.user2.clone(),
+                claimed: false,
+                amount: STAKING_AMOUNT,
+                fee: 0,
+                keeper_tip: 0,
+            },
+        ],
+        events
+    );
+
+    // The skipped claim is left untouched
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_pending_unstake_amount(&test.user2, &0)
+    );
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_requested_amount());
+    assert_eq!(0, test.contract.get_total_claimable_amount());
+    assert!(!test.contract.is_paused());
+}
+
+#[test]
+fn test_operator_events() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let destination = Address::generate(&test.env);
+    let timestamp = test.env.ledger().timestamp();
+
+    stake(&test, &test.user1, STAKING_AMOUNT * 4);
+
+    test.contract.set_withdraw_destination(&destination, &true);
+    test.contract.withdraw_amount(&STAKING_AMOUNT, &destination);
+    assert_eq!(
+        pumpbtc_staking::WithdrawAmountEvent {
+            operator: test.operator.clone(),
+            destination: destination.clone(),
+            amount: STAKING_AMOUNT,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT,
+            timestamp,
+            pending_stake_amount: STAKING_AMOUNT * 3,
+        },
+        last_event(&test, "withdr_to")
+    );
+
+    test.contract.withdraw();
+    assert_eq!(
+        pumpbtc_staking::WithdrawEvent {
+            operator: test.operator.clone(),
+            amount: STAKING_AMOUNT * 3,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT * 3,
+            timestamp,
+            pending_stake_amount: 0,
+        },
+        last_event(&test, "withdraw")
+    );
+
+    test.contract.deposit(&DEPOSIT_AMOUNT);
+    assert_eq!(
+        pumpbtc_staking::DepositEvent {
+            operator: test.operator.clone(),
+            pumpbtc_staking: test.contract.address.clone(),
+            amount: DEPOSIT_AMOUNT,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: DEPOSIT_AMOUNT,
+            timestamp,
+            total_claimable_amount: DEPOSIT_AMOUNT,
+        },
+        last_event(&test, "deposit")
+    );
+
+    stake(&test, &test.user1, STAKING_AMOUNT * 2);
+    test.contract.set_withdraw_limit(&STAKING_AMOUNT, &86400);
+    test.contract.withdraw();
+    assert_eq!(
+        pumpbtc_staking::WithdrawLimitExceededEvent {
+            operator: test.operator.clone(),
+            amount: STAKING_AMOUNT * 2,
+            remaining_allowance: STAKING_AMOUNT,
+        },
+        last_event(&test, "wd_alert")
+    );
+
+    test.contract.unpause_operator();
+    assert_eq!(
+        pumpbtc_staking::OperatorUnpausedEvent {
+            admin: test.admin.clone(),
+        },
+        last_event(&test, "op_resume")
+    );
+}
+
+#[test]
+fn test_admin_events() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let recipient = Address::generate(&test.env);
+
+    test.contract.pause();
+    assert_eq!(
+        pumpbtc_staking::PausedEvent {
+            admin: test.admin.clone(),
+        },
+        last_event(&test, "paused")
+    );
+    test.contract.unpause();
+    assert_eq!(
+        pumpbtc_staking::UnpausedEvent {
+            admin: test.admin.clone(),
+        },
+        last_event(&test, "unpaused")
+    );
+
+    let stray_amount = 12_345i128;
+    test.asset_token
+        .transfer(&test.user2, &test.contract.address, &stray_amount);
+    test.contract.reconcile();
+    assert_eq!(
+        pumpbtc_staking::ReconcileEvent {
+            admin: test.admin.clone(),
+            surplus: stray_amount,
+            unallocated_amount: stray_amount,
+        },
+        last_event(&test, "reconcile")
+    );
+
+    test.contract
+        .rescue_tokens(&test.asset_token.address, &recipient, &stray_amount);
+    assert_eq!(
+        pumpbtc_staking::RescueTokensEvent {
+            admin: test.admin.clone(),
+            token: test.asset_token.address.clone(),
+            to: recipient,
+            amount: stray_amount,
+        },
+        last_event(&test, "rescue")
+    );
+
+    let hash = test
+        .env
+        .deployer()
+        .upload_contract_wasm(pumpbtc_staking::WASM);
+    test.contract.announce_upgrade(&hash);
+    let executable_after = test.env.ledger().timestamp() + 3 * 86400;
+    assert_eq!(
+        pumpbtc_staking::AnnounceUpgradeEvent {
+            admin: test.admin.clone(),
+            hash: hash.clone(),
+            executable_after,
+        },
+        last_event(&test, "announce")
+    );
+    test.contract.cancel_upgrade();
+    assert_eq!(
+        pumpbtc_staking::CancelUpgradeEvent {
+            admin: test.admin.clone(),
+            hash: hash.clone(),
+        },
+        last_event(&test, "cancel_up")
+    );
+
+    test.contract.announce_upgrade(&hash);
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 3 * 86400;
+    });
+    test.contract.execute_upgrade();
+    assert_eq!(
+        pumpbtc_staking::UpgradeEvent {
+            admin: test.admin.clone(),
+            old_hash: None,
+            new_hash: hash.clone(),
+        },
+        last_event(&test, "upgrade")
+    );
+
+    test.contract.migrate(&2);
+    assert_eq!(
+        pumpbtc_staking::MigrateEvent {
+            admin: test.admin.clone(),
+            from_version: 2,
+            to_version: 2,
+        },
+        last_event(&test, "migrate")
+    );
+
+    let new_admin = Address::generate(&test.env);
+    test.contract.transfer_admin(&new_admin);
+    assert_eq!(
+        pumpbtc_staking::TransferAdminEvent {
+            previous_admin: test.admin.clone(),
+            new_admin: new_admin.clone(),
+        },
+        last_event(&test, "tra_admin")
+    );
+    test.contract.accept_admin();
+    assert_eq!(
+        pumpbtc_staking::AcceptAdminEvent {
+            previous_admin: test.admin.clone(),
+            new_admin: new_admin.clone(),
+        },
+        last_event(&test, "acc_admin")
+    );
+    test.contract.renounce_admin();
+    assert_eq!(
+        pumpbtc_staking::RenounceAdminEvent { admin: new_admin },
+        last_event(&test, "ren_admin")
+    );
+}
+
+#[test]
+fn test_invariant_violated_event() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    stake(&test, &test.user1, STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract.deposit(&STAKING_AMOUNT);
+
+    // Funds burned out of the contract no longer cover the deposited claims
+    test.asset_token
+        .burn(&test.contract.address, &(STAKING_AMOUNT * 2));
+    test.contract.deposit(&1);
+    assert_eq!(
+        pumpbtc_staking::InvariantViolatedEvent {
+            invariant: pumpbtc_staking::Invariant::ClaimableExceedsBalance,
+            balance: 1,
+            total_staking_amount: 0,
+            total_requested_amount: STAKING_AMOUNT,
+            total_claimable_amount: STAKING_AMOUNT + 1,
+            pending_stake_amount: STAKING_AMOUNT,
+            collected_fee: 0,
+        },
+        last_event(&test, "invariant")
+    );
+}
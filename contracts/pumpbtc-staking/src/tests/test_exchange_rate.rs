@@ -0,0 +1,209 @@
+use soroban_sdk::{testutils::Address as _, Address};
+
+use crate::tests::test_events::last_event;
+use crate::tests::test_setup::{
+    advance, pumpbtc_staking, PumpBTCStakingTest, DAY, RATE_PRECISION, STAKING_AMOUNT,
+};
+
+#[test]
+fn test_update_exchange_rate_bounds() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    advance(&test, DAY);
+    assert_eq!(RATE_PRECISION, test.contract.get_exchange_rate());
+
+    // The rate is frozen until the admin sets bounds
+    let new_rate = RATE_PRECISION + RATE_PRECISION / 100;
+    let result = test
+        .contract
+        .try_update_exchange_rate(&test.operator, &new_rate);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::RateChangeTooLarge))
+    );
+
+    let result = test.contract.try_set_exchange_rate_bounds(&10001, &DAY);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::InvalidConfig))
+    );
+    test.contract.set_exchange_rate_bounds(&100, &DAY); // 1% per day
+    assert_eq!(
+        pumpbtc_staking::ExchangeRateBounds {
+            max_change_bps: 100,
+            min_update_interval: DAY,
+        },
+        test.contract.get_exchange_rate_bounds()
+    );
+
+    let stranger = Address::generate(&test.env);
+    let result = test.contract.try_update_exchange_rate(&stranger, &new_rate);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::CallerIsNotRateUpdater
+        ))
+    );
+    let result = test.contract.try_update_exchange_rate(&test.operator, &0);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InvalidExchangeRate
+        ))
+    );
+
+    test.contract
+        .update_exchange_rate(&test.operator, &new_rate);
+    assert_eq!(
+        pumpbtc_staking::UpdateExchangeRateEvent {
+            updater: test.operator.clone(),
+            old_rate: RATE_PRECISION,
+            new_rate,
+            timestamp: DAY,
+            total_staking_amount: 0,
+        },
+        last_event(&test, "rate")
+    );
+    assert_eq!(new_rate, test.contract.get_exchange_rate());
+
+    let result = test
+        .contract
+        .try_update_exchange_rate(&test.operator, &new_rate);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::RateUpdateTooSoon))
+    );
+
+    // More than 1% of the current rate is rejected, in either direction
+    advance(&test, DAY);
+    let result = test
+        .contract
+        .try_update_exchange_rate(&test.operator, &(new_rate * 102 / 100));
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::RateChangeTooLarge))
+    );
+    let result = test
+        .contract
+        .try_update_exchange_rate(&test.operator, &(new_rate * 98 / 100));
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::RateChangeTooLarge))
+    );
+
+    // An oracle can update the rate as well
+    let oracle = Address::generate(&test.env);
+    test.contract.set_rate_oracle(&oracle);
+    assert_eq!(Some(oracle.clone()), test.contract.get_rate_oracle());
+    let oracle_rate = new_rate * 101 / 100;
+    test.contract.update_exchange_rate(&oracle, &oracle_rate);
+
+    let history = test.contract.get_exchange_rate_history(&10);
+    assert_eq!(2, history.len());
+    assert_eq!(
+        pumpbtc_staking::ExchangeRateUpdate {
+            rate: oracle_rate,
+            timestamp: 2 * DAY,
+        },
+        history.get(0).unwrap()
+    );
+    assert_eq!(
+        pumpbtc_staking::ExchangeRateUpdate {
+            rate: new_rate,
+            timestamp: DAY,
+        },
+        history.get(1).unwrap()
+    );
+    assert_eq!(1, test.contract.get_exchange_rate_history(&1).len());
+}
+
+#[test]
+fn test_stake_and_unstake_at_exchange_rate() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    test.contract.set_exchange_rate_bounds(&1000, &0);
+    let rate = RATE_PRECISION * 11 / 10;
+    test.contract.update_exchange_rate(&test.operator, &rate);
+
+    // 1.1 BTC buys one pumpBTC
+    let value = STAKING_AMOUNT * 11 / 10;
+    let pump_balance = test.pump_token.balance(&test.user1);
+    test.contract.stake(&test.user1, &(value * 2));
+    assert_eq!(
+        pumpbtc_staking::StakeEvent {
+            user: test.user1.clone(),
+            amount: value * 2,
+            pump_amount: STAKING_AMOUNT * 2,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: value * 2,
+            timestamp: 0,
+            total_staking_amount: STAKING_AMOUNT * 2,
+            pending_stake_amount: value * 2,
+        },
+        last_event(&test, "stake")
+    );
+    assert_eq!(
+        pump_balance + STAKING_AMOUNT * 2,
+        test.pump_token.balance(&test.user1)
+    );
+    assert_eq!(STAKING_AMOUNT * 2, test.contract.get_total_staking_amount());
+    assert_eq!(value * 2, test.contract.get_pending_stake_amount());
+
+    // Unstaking pays out the value at the current rate
+    test.contract.set_only_allow_stake(&false);
+    let fee = value * 500 / 10000;
+    assert_eq!(
+        value - fee,
+        test.contract.preview_unstake_instant(&STAKING_AMOUNT)
+    );
+    let balance = test.asset_token.balance(&test.user1);
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(balance + value - fee, test.asset_token.balance(&test.user1));
+    assert_eq!(fee, test.contract.get_collected_fee());
+
+    // Requests are fixed in BTC, so later rate moves do not change claims
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    let request_event: pumpbtc_staking::UnstakeRequestEvent = last_event(&test, "unstake");
+    assert_eq!(value, request_event.amount);
+    assert_eq!(STAKING_AMOUNT, request_event.pump_amount);
+    assert_eq!(value, test.contract.get_total_requested_amount());
+    assert_eq!(0, test.contract.get_total_staking_amount());
+    test.contract
+        .update_exchange_rate(&test.operator, &(rate * 105 / 100));
+
+    test.contract.deposit(&value);
+    advance(&test, 9 * DAY);
+    let balance = test.asset_token.balance(&test.user1);
+    test.contract.claim_all(&test.user1);
+    assert_eq!(
+        balance + value - value * 100 / 10000,
+        test.asset_token.balance(&test.user1)
+    );
+    assert!(!test.contract.is_paused());
+}
+
+#[test]
+fn test_staking_cap_uses_exchange_rate() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+
+    test.contract.set_exchange_rate_bounds(&10000, &0);
+    test.contract
+        .update_exchange_rate(&test.operator, &(RATE_PRECISION * 2));
+
+    // One pumpBTC is now worth two BTC of the cap
+    let result = test.contract.try_set_stake_asset_cap(&STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::StakingCapTooSmall))
+    );
+    test.contract.set_stake_asset_cap(&(STAKING_AMOUNT * 3));
+    let result = test.contract.try_stake(&test.user1, &(STAKING_AMOUNT + 1));
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::ExceedStakingCap))
+    );
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        STAKING_AMOUNT * 3 / 2,
+        test.contract.get_total_staking_amount()
+    );
+}
@@ -9,7 +9,7 @@ fn test_initialize() {
     assert_eq!(8u32, test.contract.get_asset_decimal());
     assert_eq!(100i128, test.contract.get_normal_unstake_fee());
     assert_eq!(500i128, test.contract.get_instant_unstake_fee());
-    assert_eq!(true, test.contract.get_only_allow_stake());
+    assert!(test.contract.get_only_allow_stake());
 }
 
 #[test]
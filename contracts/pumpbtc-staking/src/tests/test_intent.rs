@@ -0,0 +1,478 @@
+extern crate std;
+
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    testutils::{storage::Persistent as _, Address as _, Ledger},
+    xdr::ToXdr,
+    Address, BytesN,
+};
+
+use crate::tests::test_events::last_event;
+use crate::tests::test_setup::{
+    pumpbtc_staking, PumpBTCStakingTest, EXPIRATION_LEDGER, RATE_PRECISION, STAKING_AMOUNT,
+};
+
+fn sign_intent(
+    test: &PumpBTCStakingTest,
+    signing_key: &SigningKey,
+    intent: &pumpbtc_staking::StakingIntent,
+) -> BytesN<64> {
+    let message: std::vec::Vec<u8> = intent.clone().to_xdr(&test.env).iter().collect();
+    BytesN::from_array(&test.env, &signing_key.sign(&message).to_bytes())
+}
+
+fn register_signer(test: &PumpBTCStakingTest, user: &Address) -> SigningKey {
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let public_key = BytesN::from_array(&test.env, &signing_key.verifying_key().to_bytes());
+    test.contract.set_intent_signer(user, &public_key);
+    signing_key
+}
+
+fn build_intent(
+    test: &PumpBTCStakingTest,
+    relayer: &Address,
+    action: pumpbtc_staking::IntentAction,
+    amount: i128,
+    relayer_fee: i128,
+    nonce: u64,
+) -> pumpbtc_staking::StakingIntent {
+    pumpbtc_staking::StakingIntent {
+        action,
+        user: test.user1.clone(),
+        relayer: relayer.clone(),
+        amount,
+        relayer_fee,
+        nonce,
+        deadline: test.env.ledger().timestamp() + 3600,
+        contract: test.contract.address.clone(),
+    }
+}
+
+// 1.1 BTC per pumpBTC, so BTC values and pumpBTC amounts differ
+fn raise_exchange_rate(test: &PumpBTCStakingTest) {
+    test.contract.set_exchange_rate_bounds(&1000, &0);
+    test.contract
+        .update_exchange_rate(&test.operator, &(RATE_PRECISION * 11 / 10));
+}
+
+#[test]
+fn test_stake_and_unstake_with_intent() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+    let relayer_fee = 1_000;
+
+    test.asset_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &STAKING_AMOUNT,
+        &EXPIRATION_LEDGER,
+    );
+
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT,
+        relayer_fee,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+    assert_eq!(
+        pumpbtc_staking::SubmitIntentEvent {
+            user: test.user1.clone(),
+            relayer: relayer.clone(),
+            action: pumpbtc_staking::IntentAction::Stake,
+            amount: STAKING_AMOUNT,
+            relayer_fee,
+            nonce: 0,
+        },
+        last_event(&test, "intent")
+    );
+
+    assert_eq!(1u64, test.contract.get_intent_nonce(&test.user1));
+    assert_eq!(
+        STAKING_AMOUNT - relayer_fee,
+        test.contract.get_total_staking_amount()
+    );
+    assert_eq!(relayer_fee, test.asset_token.balance(&relayer));
+
+    test.contract.set_only_allow_stake(&false);
+
+    let amount = STAKING_AMOUNT / 2;
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::UnstakeInstant,
+        amount,
+        relayer_fee,
+        1,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+
+    assert_eq!(relayer_fee * 2, test.asset_token.balance(&relayer));
+    assert_eq!(amount * 500 / 10000, test.contract.get_collected_fee());
+}
+
+#[test]
+fn test_unstake_request_with_intent() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+    let relayer_fee = 1_000;
+
+    test.asset_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &STAKING_AMOUNT,
+        &EXPIRATION_LEDGER,
+    );
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+
+    // The relayer fee is transferred in pumpBTC through an allowance
+    test.pump_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &relayer_fee,
+        &EXPIRATION_LEDGER,
+    );
+
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::UnstakeRequest,
+        STAKING_AMOUNT,
+        relayer_fee,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    let total_supply = test.pump_token.total_supply();
+    test.contract.submit_intent(&relayer, &intent, &signature);
+
+    assert_eq!(
+        STAKING_AMOUNT - relayer_fee,
+        test.contract.get_total_requested_amount()
+    );
+    assert_eq!(relayer_fee, test.pump_token.balance(&relayer));
+    // Only the unstaked part is burned
+    assert_eq!(
+        total_supply - (STAKING_AMOUNT - relayer_fee),
+        test.pump_token.total_supply()
+    );
+}
+
+#[test]
+fn test_intent_other_relayer_rejected() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let other_relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+
+    test.asset_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &STAKING_AMOUNT,
+        &EXPIRATION_LEDGER,
+    );
+
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT,
+        1_000,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+
+    // Someone who saw the signed intent cannot submit it to take the fee
+    let result = test
+        .contract
+        .try_submit_intent(&other_relayer, &intent, &signature);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InvalidIntentTarget
+        ))
+    );
+    assert_eq!(0u64, test.contract.get_intent_nonce(&test.user1));
+
+    test.contract.submit_intent(&relayer, &intent, &signature);
+    assert_eq!(1_000, test.asset_token.balance(&relayer));
+}
+
+#[test]
+fn test_intent_fee_in_whole_asset_units() {
+    let test = PumpBTCStakingTest::setup_initialized_with_asset_decimal(6);
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+
+    test.asset_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &(STAKING_AMOUNT / 100 + 2),
+        &EXPIRATION_LEDGER,
+    );
+
+    // 150 satoshis is 1.5 units of a 6-decimal asset
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT + 150,
+        150,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    let result = test
+        .contract
+        .try_submit_intent(&relayer, &intent, &signature);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+        ))
+    );
+
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT + 200,
+        200,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+    assert_eq!(2, test.asset_token.balance(&relayer));
+}
+
+#[test]
+fn test_intent_replay_rejected() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+
+    test.asset_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &(STAKING_AMOUNT * 2),
+        &EXPIRATION_LEDGER,
+    );
+
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT,
+        0,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+
+    // The replay counter and signer are kept alive with the user's activity
+    test.env.as_contract(&test.contract.address, || {
+        for key in [
+            crate::storage::UserDataKey::IntentNonce(test.user1.clone()),
+            crate::storage::UserDataKey::IntentSigner(test.user1.clone()),
+        ] {
+            assert!(
+                test.env.storage().persistent().get_ttl(&key)
+                    >= crate::storage::PERSISTENT_LIFETIME_THRESHOLD
+            );
+        }
+    });
+
+    let result = test
+        .contract
+        .try_submit_intent(&relayer, &intent, &signature);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::InvalidIntentNonce))
+    );
+}
+
+#[test]
+fn test_intent_expired() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+
+    let mut intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT,
+        0,
+        0,
+    );
+    intent.deadline = 0;
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 1;
+    });
+
+    let signature = sign_intent(&test, &signing_key, &intent);
+    let result = test
+        .contract
+        .try_submit_intent(&relayer, &intent, &signature);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::IntentExpired))
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_intent_invalid_signature() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    register_signer(&test, &test.user1);
+
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        STAKING_AMOUNT,
+        0,
+        0,
+    );
+    let other_key = SigningKey::from_bytes(&[9u8; 32]);
+    let signature = sign_intent(&test, &other_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+}
+
+#[test]
+fn test_stake_intent_fee_at_exchange_rate() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+    raise_exchange_rate(&test);
+
+    let value = STAKING_AMOUNT * 11 / 10;
+    let relayer_fee = 1_100;
+    test.asset_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &value,
+        &EXPIRATION_LEDGER,
+    );
+
+    let asset_balance = test.asset_token.balance(&test.user1);
+    let pump_balance = test.pump_token.balance(&test.user1);
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::Stake,
+        value,
+        relayer_fee,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+
+    // The fee is paid in BTC and only the rest is staked
+    assert_eq!(relayer_fee, test.asset_token.balance(&relayer));
+    assert_eq!(asset_balance - value, test.asset_token.balance(&test.user1));
+    assert_eq!(
+        pump_balance + STAKING_AMOUNT - 1_000,
+        test.pump_token.balance(&test.user1)
+    );
+}
+
+#[test]
+fn test_unstake_request_intent_fee_at_exchange_rate() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+    raise_exchange_rate(&test);
+
+    test.contract
+        .stake(&test.user1, &(STAKING_AMOUNT * 22 / 10));
+    test.contract.set_only_allow_stake(&false);
+
+    // A 1100 satoshi fee is paid with the 1000 pumpBTC worth it
+    test.pump_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &1_000,
+        &EXPIRATION_LEDGER,
+    );
+    let pump_balance = test.pump_token.balance(&test.user1);
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::UnstakeRequest,
+        STAKING_AMOUNT,
+        1_100,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+
+    assert_eq!(1_000, test.pump_token.balance(&relayer));
+    assert_eq!(
+        pump_balance - STAKING_AMOUNT,
+        test.pump_token.balance(&test.user1)
+    );
+    assert_eq!(
+        (STAKING_AMOUNT - 1_000) * 11 / 10,
+        test.contract.get_total_requested_amount()
+    );
+
+    // The fee is bounded by the BTC value of the pumpBTC, not its amount
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::UnstakeRequest,
+        STAKING_AMOUNT,
+        STAKING_AMOUNT * 11 / 10,
+        1,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    let result = test
+        .contract
+        .try_submit_intent(&relayer, &intent, &signature);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::InvalidRelayerFee))
+    );
+}
+
+#[test]
+fn test_unstake_instant_intent_fee_at_exchange_rate() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let relayer = Address::generate(&test.env);
+    let signing_key = register_signer(&test, &test.user1);
+    raise_exchange_rate(&test);
+
+    test.contract
+        .stake(&test.user1, &(STAKING_AMOUNT * 22 / 10));
+    test.contract.set_only_allow_stake(&false);
+
+    let relayer_fee = 1_100;
+    let asset_balance = test.asset_token.balance(&test.user1);
+    let intent = build_intent(
+        &test,
+        &relayer,
+        pumpbtc_staking::IntentAction::UnstakeInstant,
+        STAKING_AMOUNT,
+        relayer_fee,
+        0,
+    );
+    let signature = sign_intent(&test, &signing_key, &intent);
+    test.contract.submit_intent(&relayer, &intent, &signature);
+
+    // The fee comes out of the BTC payout
+    let value = STAKING_AMOUNT * 11 / 10;
+    let fee = value * 500 / 10000;
+    assert_eq!(relayer_fee, test.asset_token.balance(&relayer));
+    assert_eq!(
+        asset_balance + value - fee - relayer_fee,
+        test.asset_token.balance(&test.user1)
+    );
+    assert_eq!(fee, test.contract.get_collected_fee());
+}
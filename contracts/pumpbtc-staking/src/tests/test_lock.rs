@@ -0,0 +1,274 @@
+use crate::tests::test_events::last_event;
+use crate::tests::test_setup::{
+    advance, new_staker, pumpbtc_staking, PumpBTCStakingTest, DAY, STAKING_AMOUNT,
+};
+
+#[test]
+fn test_stake_locked_boosts_rewards() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let staker = new_staker(&test);
+    let locker = new_staker(&test);
+
+    test.contract.stake(&staker, &STAKING_AMOUNT);
+    let lock_id = test.contract.stake_locked(
+        &locker,
+        &STAKING_AMOUNT,
+        &pumpbtc_staking::LockTier::Days180,
+    );
+    assert_eq!(0, lock_id);
+    assert_eq!(
+        pumpbtc_staking::StakeLockedEvent {
+            user: locker.clone(),
+            lock_id,
+            tier: pumpbtc_staking::LockTier::Days180,
+            amount: STAKING_AMOUNT,
+            boost: STAKING_AMOUNT / 2,
+            unlock_time: 180 * DAY,
+        },
+        last_event(&test, "stk_lock")
+    );
+    assert_eq!(STAKING_AMOUNT, test.pump_token.balance(&locker));
+    assert_eq!(STAKING_AMOUNT, test.pump_token.locked_balance(&locker));
+    assert_eq!(STAKING_AMOUNT / 2, test.pump_token.get_boost(&locker));
+    assert!(test.contract.get_lock_position(&locker, &lock_id).is_some());
+
+    // The 180 day tier earns 1.5x the rewards of an unlocked stake
+    test.contract
+        .deposit_rewards(&(STAKING_AMOUNT * 10_000_000_000));
+    let staker_rewards = test.contract.get_accrued_rewards(&staker);
+    let locker_rewards = test.contract.get_accrued_rewards(&locker);
+    assert!(staker_rewards > 0);
+    assert!((locker_rewards * 2 - staker_rewards * 3).abs() <= 3);
+
+    // Locked pumpBTC cannot leave the balance
+    test.contract.set_only_allow_stake(&false);
+    let result = test.contract.try_unstake_request(&locker, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientPumpBalance
+        ))
+    );
+}
+
+#[test]
+fn test_unlock_early_pays_penalty() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let locker = new_staker(&test);
+
+    let result = test.contract.try_set_lock_penalty(&10001);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::FeeShouldBeBetween0And10000
+        ))
+    );
+    test.contract.set_lock_penalty(&1000); // 10%
+    assert_eq!(1000, test.contract.get_lock_penalty());
+
+    let lock_id =
+        test.contract
+            .stake_locked(&locker, &STAKING_AMOUNT, &pumpbtc_staking::LockTier::Days30);
+    // The stake has already been swept to custody
+    test.contract.withdraw();
+    assert_eq!(0, test.contract.get_pending_stake_amount());
+    advance(&test, DAY);
+
+    let penalty = STAKING_AMOUNT / 10;
+    assert_eq!(
+        STAKING_AMOUNT - penalty,
+        test.contract.unlock(&locker, &lock_id)
+    );
+    assert_eq!(
+        pumpbtc_staking::UnlockEvent {
+            user: locker.clone(),
+            lock_id,
+            amount: STAKING_AMOUNT - penalty,
+            penalty,
+            penalty_value: penalty,
+            timestamp: DAY,
+        },
+        last_event(&test, "unlock")
+    );
+    assert_eq!(STAKING_AMOUNT - penalty, test.pump_token.balance(&locker));
+    assert_eq!(0, test.pump_token.locked_balance(&locker));
+    assert_eq!(0, test.pump_token.get_boost(&locker));
+    assert_eq!(
+        STAKING_AMOUNT - penalty,
+        test.contract.get_total_staking_amount()
+    );
+    assert_eq!(None, test.contract.get_lock_position(&locker, &lock_id));
+
+    // A released lock cannot be unlocked again
+    let result = test.contract.try_unlock(&locker, &lock_id);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::LockNotFound))
+    );
+
+    // The operator owes the penalty until it is deposited as a fee
+    assert_eq!(penalty, test.contract.get_owed_lock_penalty());
+    assert_eq!(0, test.contract.get_collected_fee());
+    let balance = test.asset_token.balance(&test.operator);
+    assert_eq!(penalty, test.contract.deposit_lock_penalty());
+    assert_eq!(
+        pumpbtc_staking::DepositLockPenaltyEvent {
+            operator: test.operator.clone(),
+            amount: penalty,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: penalty,
+            timestamp: DAY,
+        },
+        last_event(&test, "dep_lpen")
+    );
+    assert_eq!(balance - penalty, test.asset_token.balance(&test.operator));
+    assert_eq!(0, test.contract.get_owed_lock_penalty());
+    assert_eq!(penalty, test.contract.get_collected_fee());
+    assert_eq!(penalty, test.contract.get_collected_lock_penalty());
+    assert_eq!(penalty, test.contract.get_lifetime_lock_penalty());
+    assert_eq!(0, test.contract.check_solvency().surplus);
+
+    let result = test.contract.try_deposit_lock_penalty();
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::NoOwedLockPenalty))
+    );
+}
+
+#[test]
+fn test_unlock_early_takes_penalty_from_pending_stake() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let locker = new_staker(&test);
+    let staker = new_staker(&test);
+    test.contract.set_lock_penalty(&1000); // 10%
+    let penalty = STAKING_AMOUNT / 10;
+
+    // The backing is still in the pending stake, so the penalty is a fee at once
+    let lock_id =
+        test.contract
+            .stake_locked(&locker, &STAKING_AMOUNT, &pumpbtc_staking::LockTier::Days30);
+    test.contract.unlock(&locker, &lock_id);
+    assert_eq!(0, test.contract.get_owed_lock_penalty());
+    assert_eq!(penalty, test.contract.get_collected_lock_penalty());
+    assert_eq!(
+        STAKING_AMOUNT - penalty,
+        test.contract.get_pending_stake_amount()
+    );
+    assert_eq!(0, test.contract.check_solvency().surplus);
+
+    // Only the part the operator has already withdrawn is owed
+    let lock_id =
+        test.contract
+            .stake_locked(&locker, &STAKING_AMOUNT, &pumpbtc_staking::LockTier::Days30);
+    test.contract.withdraw();
+    test.contract.stake(&staker, &(penalty / 2));
+    test.contract.unlock(&locker, &lock_id);
+    assert_eq!(penalty / 2, test.contract.get_owed_lock_penalty());
+    assert_eq!(
+        penalty + penalty / 2,
+        test.contract.get_collected_lock_penalty()
+    );
+    assert_eq!(0, test.contract.get_pending_stake_amount());
+    assert_eq!(0, test.contract.check_solvency().surplus);
+}
+
+#[test]
+fn test_unlock_after_term_is_free() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let locker = new_staker(&test);
+    test.contract.set_lock_penalty(&1000);
+
+    let first_id =
+        test.contract
+            .stake_locked(&locker, &STAKING_AMOUNT, &pumpbtc_staking::LockTier::Days30);
+    let second_id =
+        test.contract
+            .stake_locked(&locker, &STAKING_AMOUNT, &pumpbtc_staking::LockTier::Days90);
+    assert_eq!(1, second_id);
+    assert_eq!(STAKING_AMOUNT * 2, test.pump_token.locked_balance(&locker));
+
+    advance(&test, 30 * DAY);
+    assert_eq!(STAKING_AMOUNT, test.contract.unlock(&locker, &first_id));
+    assert_eq!(0, test.contract.get_owed_lock_penalty());
+    assert_eq!(STAKING_AMOUNT, test.pump_token.locked_balance(&locker));
+    assert_eq!(STAKING_AMOUNT / 4, test.pump_token.get_boost(&locker));
+
+    assert_eq!(None, test.contract.get_lock_position(&locker, &first_id));
+    let position = test
+        .contract
+        .get_lock_position(&locker, &second_id)
+        .unwrap();
+    assert_eq!(second_id, position.id);
+    assert_eq!(90 * DAY, position.unlock_time);
+}
+
+#[test]
+fn test_lock_positions_skip_released_locks() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let locker = new_staker(&test);
+    assert_eq!(0, test.contract.get_lock_positions(&locker).len());
+
+    let lock_ids = [
+        pumpbtc_staking::LockTier::Days30,
+        pumpbtc_staking::LockTier::Days90,
+        pumpbtc_staking::LockTier::Days180,
+    ]
+    .map(|tier| test.contract.stake_locked(&locker, &STAKING_AMOUNT, &tier));
+    assert_eq!(3, test.contract.get_next_lock_id(&locker));
+
+    advance(&test, 30 * DAY);
+    test.contract.unlock(&locker, &lock_ids[1]);
+
+    let positions = test.contract.get_lock_positions(&locker);
+    assert_eq!(2, positions.len());
+    assert_eq!(lock_ids[0], positions.get_unchecked(0).id);
+    assert_eq!(lock_ids[2], positions.get_unchecked(1).id);
+    assert_eq!(
+        pumpbtc_staking::LockTier::Days180,
+        positions.get_unchecked(1).tier
+    );
+    // Released ids are not handed out again
+    assert_eq!(3, test.contract.get_next_lock_id(&locker));
+}
+
+#[test]
+fn test_unlock_unknown_lock_fails() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let locker = new_staker(&test);
+    let other = new_staker(&test);
+
+    let lock_id =
+        test.contract
+            .stake_locked(&locker, &STAKING_AMOUNT, &pumpbtc_staking::LockTier::Days30);
+
+    let result = test.contract.try_unlock(&locker, &(lock_id + 1));
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::LockNotFound))
+    );
+
+    // Lock ids are per user
+    let result = test.contract.try_unlock(&other, &lock_id);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::LockNotFound))
+    );
+    assert_eq!(STAKING_AMOUNT, test.pump_token.locked_balance(&locker));
+}
+
+#[test]
+fn test_stake_locked_needs_the_pump_token_hook() {
+    let test = PumpBTCStakingTest::setup_initialized_with_sac_pump();
+    let result = test.contract.try_stake_locked(
+        &test.user1,
+        &STAKING_AMOUNT,
+        &pumpbtc_staking::LockTier::Days30,
+    );
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::UnsupportedPumpToken
+        ))
+    );
+    assert_eq!(0, test.contract.get_total_staking_amount());
+}
@@ -0,0 +1,207 @@
+use soroban_sdk::{testutils::Address as _, Address};
+
+use crate::solvency::daily_obligation;
+use crate::tests::test_events::last_event;
+use crate::tests::test_setup::{
+    advance, new_staker, pumpbtc_staking, PumpBTCStakingTest, DAY, RATE_PRECISION, STAKING_AMOUNT,
+};
+use crate::utils::get_maturity_day;
+
+#[test]
+fn test_report_loss_access_and_bounds() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let staker = new_staker(&test);
+    test.contract.stake(&staker, &STAKING_AMOUNT);
+
+    let stranger = Address::generate(&test.env);
+    let result = test.contract.try_report_loss(&stranger, &1);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::CallerIsNotAdminOrOperator
+        ))
+    );
+    let result = test.contract.try_report_loss(&test.operator, &0);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::NegativeAmountNotAllowed
+        ))
+    );
+    let result = test
+        .contract
+        .try_report_loss(&test.operator, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::LossExceedsBacking))
+    );
+
+    // The admin may report a loss as well
+    test.contract
+        .report_loss(&test.admin, &(STAKING_AMOUNT / 10));
+    assert_eq!(
+        pumpbtc_staking::ReportLossEvent {
+            reporter: test.admin.clone(),
+            amount: STAKING_AMOUNT / 10,
+            backing: STAKING_AMOUNT,
+            claim_ratio: RATE_PRECISION / 10 * 9,
+            old_rate: RATE_PRECISION,
+            new_rate: RATE_PRECISION / 10 * 9,
+            timestamp: 0,
+            total_requested_amount: 0,
+        },
+        last_event(&test, "loss")
+    );
+    assert_eq!(RATE_PRECISION / 10 * 9, test.contract.get_claim_ratio());
+    assert_eq!(RATE_PRECISION / 10 * 9, test.contract.get_exchange_rate());
+}
+
+#[test]
+fn test_report_loss_is_shared_pro_rata() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let requester = new_staker(&test);
+    let holder = new_staker(&test);
+    test.contract.stake(&requester, &(STAKING_AMOUNT * 4));
+    test.contract.stake(&holder, &(STAKING_AMOUNT * 4));
+    test.contract.set_only_allow_stake(&false);
+
+    // Half of the requester's stake is waiting to be claimed when a quarter of
+    // the backing is lost
+    test.contract
+        .unstake_request(&requester, &(STAKING_AMOUNT * 2));
+    test.contract
+        .report_loss(&test.operator, &(STAKING_AMOUNT * 2));
+    assert_eq!(RATE_PRECISION / 4 * 3, test.contract.get_claim_ratio());
+    assert_eq!(RATE_PRECISION / 4 * 3, test.contract.get_exchange_rate());
+    assert_eq!(
+        STAKING_AMOUNT / 2 * 3,
+        test.contract.get_total_requested_amount()
+    );
+    assert_eq!(
+        STAKING_AMOUNT / 2 * 3,
+        test.contract.get_pending_unstake_amount(&requester, &0)
+    );
+    let schedule = test.contract.get_funding_schedule(&10);
+    assert_eq!(
+        STAKING_AMOUNT / 2 * 3,
+        schedule.get_unchecked(9).outstanding_amount
+    );
+
+    // Requests made after the loss are already valued at the lower rate and
+    // are not cut again
+    test.contract
+        .unstake_request(&holder, &(STAKING_AMOUNT * 4));
+    assert_eq!(
+        STAKING_AMOUNT * 3,
+        test.contract.get_pending_unstake_amount(&holder, &0)
+    );
+    assert_eq!(
+        STAKING_AMOUNT / 2 * 9,
+        test.contract.get_total_requested_amount()
+    );
+
+    advance(&test, 9 * DAY);
+    test.contract.deposit(&(STAKING_AMOUNT / 2 * 9));
+
+    // Both users lose a quarter of what they staked, less the 1% claim fee
+    let balance = test.asset_token.balance(&requester);
+    test.contract.claim_all(&requester);
+    let claimed = STAKING_AMOUNT / 2 * 3;
+    assert_eq!(
+        balance + claimed - claimed / 100,
+        test.asset_token.balance(&requester)
+    );
+    let remaining = test.pump_token.balance(&requester);
+    assert_eq!(
+        STAKING_AMOUNT * 3,
+        claimed + remaining * test.contract.get_exchange_rate() / RATE_PRECISION
+    );
+
+    let balance = test.asset_token.balance(&holder);
+    test.contract.claim_slot(&holder, &0);
+    let claimed = STAKING_AMOUNT * 3;
+    assert_eq!(
+        balance + claimed - claimed / 100,
+        test.asset_token.balance(&holder)
+    );
+
+    assert_eq!(0, test.contract.get_total_requested_amount());
+    assert_eq!(0, test.contract.get_total_claimable_amount());
+    assert!(!test.contract.is_paused());
+}
+
+#[test]
+fn test_report_loss_cuts_funded_requests() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let requester = new_staker(&test);
+    let holder = new_staker(&test);
+    test.contract.stake(&requester, &(STAKING_AMOUNT * 4));
+    test.contract.stake(&holder, &(STAKING_AMOUNT * 4));
+    test.contract.set_only_allow_stake(&false);
+
+    // The request is already funded when a quarter of the backing is lost
+    test.contract
+        .unstake_request(&requester, &(STAKING_AMOUNT * 2));
+    test.contract.deposit(&(STAKING_AMOUNT * 2));
+    let pending_stake_amount = test.contract.get_pending_stake_amount();
+    test.contract
+        .report_loss(&test.operator, &(STAKING_AMOUNT * 2));
+    assert_eq!(
+        STAKING_AMOUNT / 2 * 3,
+        test.contract.get_total_requested_amount()
+    );
+    assert_eq!(
+        STAKING_AMOUNT / 2 * 3,
+        test.contract.get_total_claimable_amount()
+    );
+    // The deposit freed by the cut backs pumpBTC again
+    assert_eq!(
+        pending_stake_amount + STAKING_AMOUNT / 2,
+        test.contract.get_pending_stake_amount()
+    );
+    assert_eq!(0, test.contract.check_solvency().surplus);
+
+    advance(&test, 9 * DAY);
+    let balance = test.asset_token.balance(&requester);
+    test.contract.claim_all(&requester);
+    let claimed = STAKING_AMOUNT / 2 * 3;
+    assert_eq!(
+        balance + claimed - claimed / 100,
+        test.asset_token.balance(&requester)
+    );
+
+    // Nothing is left stranded in the claim pool
+    assert_eq!(0, test.contract.get_total_requested_amount());
+    assert_eq!(0, test.contract.get_total_claimable_amount());
+    assert_eq!(0, test.contract.check_solvency().surplus);
+    assert!(!test.contract.is_paused());
+}
+
+#[test]
+fn test_report_loss_after_maturity_keeps_schedule_balanced() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let requester = new_staker(&test);
+    let holder = new_staker(&test);
+    test.contract.stake(&requester, &(STAKING_AMOUNT * 4));
+    test.contract.stake(&holder, &(STAKING_AMOUNT * 4));
+    test.contract.set_only_allow_stake(&false);
+
+    let maturity_day = get_maturity_day(test.env.ledger().timestamp());
+    test.contract
+        .unstake_request(&requester, &(STAKING_AMOUNT * 2));
+
+    // The request matured yesterday and is still unclaimed when the loss hits
+    advance(&test, 10 * DAY);
+    test.contract
+        .report_loss(&test.operator, &(STAKING_AMOUNT * 2));
+    test.contract.deposit(&(STAKING_AMOUNT / 2 * 3));
+    test.contract.claim_all(&requester);
+
+    let obligation = test.env.as_contract(&test.contract.address, || {
+        daily_obligation(&test.env, maturity_day).unwrap()
+    });
+    assert_eq!(STAKING_AMOUNT / 2 * 3, obligation.requested_amount);
+    assert_eq!(STAKING_AMOUNT / 2 * 3, obligation.claimed_amount);
+    assert_eq!(0, obligation.outstanding_amount);
+    assert_eq!(0, test.contract.get_funding_shortfall());
+}
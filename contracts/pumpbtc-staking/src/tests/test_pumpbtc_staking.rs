@@ -1,22 +1,17 @@
 use soroban_sdk::{
-    testutils::{Ledger, MockAuth, MockAuthInvoke},
-    IntoVal,
+    testutils::{storage::Persistent as _, Address as _, Ledger, MockAuth, MockAuthInvoke},
+    Address, IntoVal,
 };
 
-use crate::tests::test_setup::{PumpBTCStakingTest, pumpbtc_staking, STAKING_AMOUNT, EXPIRATION_LEDGER};
+use crate::tests::test_setup::{
+    advance, pumpbtc_staking, PumpBTCStakingTest, DAY, EXPIRATION_LEDGER, STAKING_AMOUNT,
+};
 
 /// TODO: need add edge case test and error test
 #[test]
 fn test_stake() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
-
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -31,11 +26,10 @@ fn test_stake() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -60,15 +54,45 @@ fn test_stake() {
 }
 
 #[test]
-fn test_unstake_request() {
+fn test_stake_from_allowance() {
     let test = PumpBTCStakingTest::setup_initialized();
 
+    // Without an allowance the pull must fail
+    let result = test
+        .contract
+        .try_stake_from_allowance(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientAllowance
+        ))
+    );
+
+    let pump_balance = test.pump_token.balance(&test.user1);
     test.asset_token.approve(
         &test.user1,
         &test.contract.address,
         &STAKING_AMOUNT,
         &EXPIRATION_LEDGER,
     );
+    test.contract
+        .stake_from_allowance(&test.user1, &STAKING_AMOUNT);
+
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_staking_amount());
+    assert_eq!(
+        pump_balance + STAKING_AMOUNT,
+        test.pump_token.balance(&test.user1)
+    );
+    assert_eq!(
+        0,
+        test.asset_token
+            .allowance(&test.user1, &test.contract.address)
+    );
+}
+
+#[test]
+fn test_unstake_request() {
+    let test = PumpBTCStakingTest::setup_initialized();
 
     test.contract
         .mock_auths(&[MockAuth {
@@ -84,11 +108,10 @@ fn test_unstake_request() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -143,13 +166,6 @@ fn test_unstake_request() {
 fn test_unstake_instant() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
-
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -164,11 +180,10 @@ fn test_unstake_instant() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -236,13 +251,6 @@ fn test_unstake_instant() {
 fn test_collect_fee() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
-
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -257,11 +265,10 @@ fn test_collect_fee() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -338,13 +345,6 @@ fn test_collect_fee() {
 fn test_claim_slot() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
-
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -359,11 +359,10 @@ fn test_claim_slot() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -417,9 +416,34 @@ fn test_claim_slot() {
     assert_eq!(STAKING_AMOUNT, pending_amount);
 
     test.env.ledger().with_mut(|ledger| {
-        ledger.timestamp = ledger.timestamp + 9 * 24 * 60 * 60; // 9 days
+        ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
     });
 
+    // The operator has not deposited for the matured claim yet
+    let result = test.contract.try_claim_slot(&test.user1, &slot);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::ClaimNotFunded))
+    );
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_requested_amount());
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_pending_unstake_amount(&test.user1, &slot)
+    );
+}
+
+#[test]
+fn test_claim_slot_funded() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+
+    let slot = test.env.ledger().timestamp() as u32;
+    advance(&test, 9 * DAY);
+    test.contract.deposit(&STAKING_AMOUNT);
+
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -459,13 +483,6 @@ fn test_claim_slot() {
 fn test_claim_all() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
-
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -480,11 +497,10 @@ fn test_claim_all() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -533,9 +549,29 @@ fn test_claim_all() {
         .unstake_request(&test.user1, &STAKING_AMOUNT);
 
     test.env.ledger().with_mut(|ledger| {
-        ledger.timestamp = ledger.timestamp + 9 * 24 * 60 * 60; // 9 days
+        ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
     });
 
+    // The operator has not deposited for the matured claim yet
+    let result = test.contract.try_claim_all(&test.user1);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::ClaimNotFunded))
+    );
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_requested_amount());
+}
+
+#[test]
+fn test_claim_all_funded() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+
+    advance(&test, 9 * DAY);
+    test.contract.deposit(&STAKING_AMOUNT);
+
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -564,14 +600,7 @@ fn test_claim_all() {
 #[test]
 fn test_stake_exceeds_cap() {
     let test = PumpBTCStakingTest::setup_initialized();
-    let stake_amount = STAKING_AMOUNT * 100000 as i128;
-
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &stake_amount,
-        &EXPIRATION_LEDGER,
-    );
+    let stake_amount = STAKING_AMOUNT * 100000;
 
     let result = test.contract.try_stake(&test.user1, &stake_amount);
     assert_eq!(
@@ -581,16 +610,38 @@ fn test_stake_exceeds_cap() {
 }
 
 #[test]
-fn test_unstake_when_only_allow_stake() {
+fn test_token_call_errors() {
     let test = PumpBTCStakingTest::setup_initialized();
+    let user = Address::generate(&test.env);
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
+    let result = test.contract.try_stake(&user, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientAssetBalance
+        ))
     );
 
+    test.asset_token.mint(&user, &STAKING_AMOUNT);
+    test.contract.stake(&user, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+
+    let result = test
+        .contract
+        .try_unstake_request(&user, &(STAKING_AMOUNT * 2));
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InsufficientPumpBalance
+        ))
+    );
+    assert_eq!(STAKING_AMOUNT, test.pump_token.balance(&user));
+}
+
+#[test]
+fn test_unstake_when_only_allow_stake() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -605,11 +656,10 @@ fn test_unstake_when_only_allow_stake() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -682,13 +732,6 @@ fn test_set_invalid_fee() {
 fn test_claim_slot_too_early() {
     let test = PumpBTCStakingTest::setup_initialized();
 
-    test.asset_token.approve(
-        &test.user1,
-        &test.contract.address,
-        &STAKING_AMOUNT,
-        &EXPIRATION_LEDGER,
-    );
-
     test.contract
         .mock_auths(&[MockAuth {
             address: &test.user1,
@@ -703,11 +746,10 @@ fn test_claim_slot_too_early() {
                 sub_invokes: &[
                     MockAuthInvoke {
                         contract: &test.asset_token.address,
-                        fn_name: "transfer_from",
+                        fn_name: "transfer",
                         args: soroban_sdk::vec![
                             &test.env,
-                            test.contract.address.into_val(&test.env), // spender
-                            test.user1.into_val(&test.env),            // from
+                            test.user1.into_val(&test.env), // from
                             test.contract.address.into_val(&test.env), // to
                             STAKING_AMOUNT.into_val(&test.env),
                         ],
@@ -792,3 +834,200 @@ fn test_unpause_when_not_paused() {
         ))
     );
 }
+
+#[test]
+fn test_claim_for() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    let keeper = Address::generate(&test.env);
+
+    for user in [&test.user1, &test.user2] {
+        test.contract.stake(user, &STAKING_AMOUNT);
+    }
+
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract.unstake_request(&test.user2, &STAKING_AMOUNT);
+
+    test.contract.deposit(&(STAKING_AMOUNT * 2));
+
+    // Only user1 opts in, with a 1% keeper tip
+    test.contract.set_keeper_tip(&test.user1, &true, &100);
+    assert_eq!(Some(100i128), test.contract.get_keeper_tip(&test.user1));
+    assert_eq!(None, test.contract.get_keeper_tip(&test.user2));
+    test.env.as_contract(&test.contract.address, || {
+        let key = crate::storage::UserDataKey::KeeperTip(test.user1.clone());
+        assert!(
+            test.env.storage().persistent().get_ttl(&key)
+                >= crate::storage::PERSISTENT_LIFETIME_THRESHOLD
+        );
+    });
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
+    });
+
+    let user1_balance = test.asset_token.balance(&test.user1);
+    let users = soroban_sdk::vec![&test.env, test.user1.clone(), test.user2.clone()];
+    assert_eq!(1u32, test.contract.claim_for(&keeper, &users));
+
+    let fee = STAKING_AMOUNT * 100 / 10000;
+    let keeper_tip = (STAKING_AMOUNT - fee) * 100 / 10000;
+    assert_eq!(
+        user1_balance + STAKING_AMOUNT - fee - keeper_tip,
+        test.asset_token.balance(&test.user1)
+    );
+    assert_eq!(keeper_tip, test.asset_token.balance(&keeper));
+    assert_eq!(fee, test.contract.get_collected_fee());
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_requested_amount());
+
+    // user2 can still claim on their own
+    test.contract.claim_all(&test.user2);
+    assert_eq!(0i128, test.contract.get_total_requested_amount());
+}
+
+#[test]
+fn test_claim_without_deposit_fails() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
+    });
+
+    // Nothing was deposited by the operator, so the pending stake of other
+    // stakers must not pay for the claim
+    let balance = test.asset_token.balance(&test.user1);
+    let result = test.contract.try_claim_all(&test.user1);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::ClaimNotFunded))
+    );
+    assert_eq!(balance, test.asset_token.balance(&test.user1));
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_pending_unstake_amount(&test.user1, &0)
+    );
+    assert!(!test.contract.is_paused());
+    assert_eq!(None, test.contract.get_invariant_violation());
+}
+
+#[test]
+fn test_lost_balance_pauses() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract.deposit(&STAKING_AMOUNT);
+    assert_eq!(None, test.contract.get_invariant_violation());
+
+    // The deposited funds leave the contract outside of its accounting
+    let balance = test.asset_token.balance(&test.contract.address);
+    test.asset_token.burn(&test.contract.address, &balance);
+    test.contract.deposit(&1);
+    assert!(test.contract.is_paused());
+    assert_eq!(
+        Some(pumpbtc_staking::Invariant::ClaimableExceedsBalance),
+        test.contract.get_invariant_violation()
+    );
+
+    test.contract.unpause();
+    assert!(!test.contract.is_paused());
+    assert_eq!(None, test.contract.get_invariant_violation());
+}
+
+#[test]
+fn test_set_invalid_keeper_tip() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    let result = test.contract.try_set_keeper_tip(&test.user1, &true, &10000);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::FeeShouldBeBetween0And10000
+        ))
+    );
+}
+
+#[test]
+fn test_fee_buckets() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.stake(&test.user1, &(STAKING_AMOUNT * 2));
+    test.contract.set_only_allow_stake(&false);
+
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
+    });
+    test.contract.deposit(&STAKING_AMOUNT);
+    test.contract.claim_all(&test.user1);
+
+    let normal_fee = STAKING_AMOUNT * 100 / 10000;
+    let instant_fee = STAKING_AMOUNT * 500 / 10000;
+    assert_eq!(normal_fee, test.contract.get_collected_normal_fee());
+    assert_eq!(instant_fee, test.contract.get_collected_instant_fee());
+    assert_eq!(normal_fee + instant_fee, test.contract.get_collected_fee());
+
+    test.contract.collect_fee();
+
+    assert_eq!(0i128, test.contract.get_collected_normal_fee());
+    assert_eq!(0i128, test.contract.get_collected_instant_fee());
+    assert_eq!(normal_fee, test.contract.get_lifetime_normal_fee());
+    assert_eq!(instant_fee, test.contract.get_lifetime_instant_fee());
+}
+
+#[test]
+fn test_unstake_instant_with_fee_curve() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    test.contract.set_instant_fee_curve(&100, &1000);
+    assert_eq!(
+        Some(pumpbtc_staking::InstantFeeCurve {
+            base_fee: 100,
+            max_fee: 1000,
+        }),
+        test.contract.get_instant_fee_curve()
+    );
+
+    test.contract.stake(&test.user1, &(STAKING_AMOUNT * 2));
+    test.contract.set_only_allow_stake(&false);
+
+    // Half of the buffer: 100 + (1000 - 100) * 50% = 550 bps
+    let first_fee = STAKING_AMOUNT * 550 / 10000;
+    assert_eq!(
+        STAKING_AMOUNT - first_fee,
+        test.contract.preview_unstake_instant(&STAKING_AMOUNT)
+    );
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(first_fee, test.contract.get_collected_fee());
+
+    // The whole remaining buffer is charged the max fee
+    assert_eq!(
+        STAKING_AMOUNT - STAKING_AMOUNT * 1000 / 10000,
+        test.contract.preview_unstake_instant(&STAKING_AMOUNT)
+    );
+
+    // Removing the curve falls back to the flat fee
+    test.contract.remove_instant_fee_curve();
+    assert_eq!(
+        STAKING_AMOUNT - STAKING_AMOUNT * 500 / 10000,
+        test.contract.preview_unstake_instant(&STAKING_AMOUNT)
+    );
+}
+
+#[test]
+fn test_set_invalid_fee_curve() {
+    let test = PumpBTCStakingTest::setup_initialized();
+
+    let result = test.contract.try_set_instant_fee_curve(&1000, &100);
+    assert_eq!(
+        result,
+        Err(Ok(pumpbtc_staking::PumpBTCStakingError::InvalidConfig))
+    );
+}
@@ -0,0 +1,85 @@
+use crate::tests::test_events::last_event;
+use crate::tests::test_setup::{pumpbtc_staking, PumpBTCStakingTest, STAKING_AMOUNT};
+
+#[test]
+fn test_deposit_and_claim_rewards() {
+    let test = PumpBTCStakingTest::setup_initialized();
+    assert_eq!(0, test.contract.claim_rewards(&test.user1));
+
+    // Admin, user1 and user2 each hold a third of the pumpBTC supply
+    let reward = STAKING_AMOUNT * 3;
+    test.contract.deposit_rewards(&reward);
+    assert_eq!(
+        pumpbtc_staking::DepositRewardsEvent {
+            operator: test.operator.clone(),
+            amount: reward,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: reward,
+            timestamp: 0,
+            reward_reserve: reward,
+        },
+        last_event(&test, "dep_rwd")
+    );
+    assert_eq!(reward, test.contract.get_reward_reserve());
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_accrued_rewards(&test.user1)
+    );
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_accrued_rewards(&test.user2)
+    );
+
+    // Rewards earned so far stay with user2, later ones follow the balance
+    let pump_balance = test.pump_token.balance(&test.user2);
+    test.pump_token
+        .transfer(&test.user2, &test.user1, &pump_balance);
+    test.contract.deposit_rewards(&reward);
+    assert_eq!(
+        STAKING_AMOUNT * 3,
+        test.contract.get_accrued_rewards(&test.user1)
+    );
+    assert_eq!(
+        STAKING_AMOUNT,
+        test.contract.get_accrued_rewards(&test.user2)
+    );
+
+    let balance = test.asset_token.balance(&test.user1);
+    assert_eq!(STAKING_AMOUNT * 3, test.contract.claim_rewards(&test.user1));
+    assert_eq!(
+        pumpbtc_staking::ClaimRewardsEvent {
+            user: test.user1.clone(),
+            amount: STAKING_AMOUNT * 3,
+            asset_token: test.asset_token.address.clone(),
+            asset_amount: STAKING_AMOUNT * 3,
+            timestamp: 0,
+            reward_reserve: STAKING_AMOUNT * 3,
+        },
+        last_event(&test, "claim_rwd")
+    );
+    assert_eq!(
+        balance + STAKING_AMOUNT * 3,
+        test.asset_token.balance(&test.user1)
+    );
+    assert_eq!(0, test.contract.get_accrued_rewards(&test.user1));
+    assert_eq!(0, test.contract.claim_rewards(&test.user1));
+
+    // Unclaimed rewards are backed by the contract balance
+    let report = test.contract.check_solvency();
+    assert_eq!(STAKING_AMOUNT * 3, report.reward_reserve);
+    assert_eq!(0, report.surplus);
+}
+
+#[test]
+fn test_rewards_need_the_pump_token_hook() {
+    let test = PumpBTCStakingTest::setup_initialized_with_sac_pump();
+    let result = test.contract.try_deposit_rewards(&STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::UnsupportedPumpToken
+        ))
+    );
+    assert_eq!(0, test.contract.get_reward_reserve());
+    assert_eq!(0, test.contract.get_accrued_rewards(&test.user1));
+}
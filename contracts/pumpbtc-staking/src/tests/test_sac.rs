@@ -0,0 +1,188 @@
+use soroban_sdk::{testutils::Address as _, testutils::Ledger, token, Address};
+
+use crate::tests::test_setup::{
+    pumpbtc_staking, PumpBTCStakingTest, EXPIRATION_LEDGER, STAKING_AMOUNT,
+};
+
+// Stellar Asset Contracts use 7 decimals
+const SAC_STAKING_AMOUNT: i128 = STAKING_AMOUNT / 10;
+
+fn sac_admin<'a>(test: &PumpBTCStakingTest<'a>) -> token::StellarAssetClient<'a> {
+    token::StellarAssetClient::new(&test.env, &test.asset_token.address)
+}
+
+#[test]
+fn test_stake_and_claim_with_sac_asset() {
+    let test = PumpBTCStakingTest::setup_initialized_with_sac_asset();
+    assert_eq!(7, test.contract.get_asset_decimal());
+    assert_eq!(
+        pumpbtc_staking::PumpTokenKind::Minter,
+        test.contract.get_pump_token_kind()
+    );
+
+    let balance = test.asset_token.balance(&test.user1);
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        balance - SAC_STAKING_AMOUNT,
+        test.asset_token.balance(&test.user1)
+    );
+    assert_eq!(
+        SAC_STAKING_AMOUNT,
+        test.asset_token.balance(&test.contract.address)
+    );
+
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract.deposit(&STAKING_AMOUNT);
+
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += 9 * 24 * 60 * 60; // 9 days
+    });
+    test.contract.claim_all(&test.user1);
+
+    // 1% normal unstake fee, paid out in 7 decimals
+    let payout = STAKING_AMOUNT - STAKING_AMOUNT * 100 / 10000;
+    assert_eq!(
+        balance - SAC_STAKING_AMOUNT + payout / 10,
+        test.asset_token.balance(&test.user1)
+    );
+    assert!(!test.contract.is_paused());
+}
+
+#[test]
+fn test_sac_asset_authorization() {
+    let test = PumpBTCStakingTest::setup_initialized_with_sac_asset();
+
+    // A deauthorized trustline cannot send the asset
+    sac_admin(&test).set_authorized(&test.user1, &false);
+    let result = test.contract.try_stake(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::AssetTransferFailed
+        ))
+    );
+
+    sac_admin(&test).set_authorized(&test.user1, &true);
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+
+    // Nor receive it, and the unstake stays in place
+    sac_admin(&test).set_authorized(&test.user1, &false);
+    let result = test
+        .contract
+        .try_unstake_instant(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::AssetTransferFailed
+        ))
+    );
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_staking_amount());
+
+    // A frozen contract balance blocks operator withdrawals
+    sac_admin(&test).set_authorized(&test.user1, &true);
+    sac_admin(&test).set_authorized(&test.contract.address, &false);
+    let result = test.contract.try_withdraw();
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::AssetTransferFailed
+        ))
+    );
+    assert_eq!(STAKING_AMOUNT, test.contract.get_pending_stake_amount());
+}
+
+#[test]
+fn test_sac_pump_mint_and_burn() {
+    let test = PumpBTCStakingTest::setup_initialized_with_sac_pump();
+    assert_eq!(
+        pumpbtc_staking::PumpTokenKind::StellarAsset,
+        test.contract.get_pump_token_kind()
+    );
+    assert_eq!(7, test.pump_token.decimals());
+
+    let pump_balance = test.pump_token.balance(&test.user1);
+    test.contract.stake(&test.user1, &(STAKING_AMOUNT * 2));
+    assert_eq!(
+        pump_balance + SAC_STAKING_AMOUNT * 2,
+        test.pump_token.balance(&test.user1)
+    );
+
+    test.contract.set_only_allow_stake(&false);
+    test.contract.unstake_request(&test.user1, &STAKING_AMOUNT);
+    test.contract.unstake_instant(&test.user1, &STAKING_AMOUNT);
+    assert_eq!(pump_balance, test.pump_token.balance(&test.user1));
+    assert_eq!(STAKING_AMOUNT, test.contract.get_total_requested_amount());
+    assert_eq!(0, test.contract.get_total_staking_amount());
+
+    // Less than one pumpBTC unit cannot be minted or burned
+    let result = test.contract.try_unstake_request(&test.user1, &5);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::AmountNotRepresentable
+        ))
+    );
+}
+
+#[test]
+fn test_sac_pump_relayed_burn_needs_allowance() {
+    let test = PumpBTCStakingTest::setup_initialized_with_sac_pump();
+    test.contract.stake(&test.user1, &STAKING_AMOUNT);
+    test.contract.set_only_allow_stake(&false);
+
+    let pump_balance = test.pump_token.balance(&test.user1);
+    test.pump_token.approve(
+        &test.user1,
+        &test.contract.address,
+        &SAC_STAKING_AMOUNT,
+        &EXPIRATION_LEDGER,
+    );
+    test.env.as_contract(&test.contract.address, || {
+        crate::tokens::burn_pump(&test.env, &test.user1, STAKING_AMOUNT * 2, true).unwrap_err();
+        crate::tokens::burn_pump(&test.env, &test.user1, STAKING_AMOUNT, true).unwrap();
+    });
+    assert_eq!(
+        pump_balance - SAC_STAKING_AMOUNT,
+        test.pump_token.balance(&test.user1)
+    );
+    assert_eq!(
+        0,
+        test.pump_token
+            .allowance(&test.user1, &test.contract.address)
+    );
+}
+
+#[test]
+fn test_initialize_sac_pump_checks() {
+    // A 7 decimal SAC cannot be driven through the minter interface
+    let test = PumpBTCStakingTest::setup_with_sac(true);
+    let result = test.contract.try_initialize(
+        &test.admin,
+        &test.pump_token.address,
+        &test.asset_token.address,
+    );
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InvalidPumpTokenDecimal
+        ))
+    );
+
+    // The staking contract must administer the pumpBTC SAC
+    let other_admin = Address::generate(&test.env);
+    let other_pump = test
+        .env
+        .register_stellar_asset_contract_v2(other_admin)
+        .address();
+    let result =
+        test.contract
+            .try_initialize_sac_pump(&test.admin, &other_pump, &test.asset_token.address);
+    assert_eq!(
+        result,
+        Err(Ok(
+            pumpbtc_staking::PumpBTCStakingError::InvalidPumpTokenAdmin
+        ))
+    );
+}
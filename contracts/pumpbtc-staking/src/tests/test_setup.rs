@@ -1,11 +1,16 @@
 use soroban_sdk::{
-    testutils::{Address as _},
+    testutils::{Address as _, IssuerFlags, Ledger},
     Address, Env, IntoVal,
 };
 
 pub const STAKING_AMOUNT: i128 = 100_000_000;
 pub const DEPOSIT_AMOUNT: i128 = 1_000_000_000;
 pub const EXPIRATION_LEDGER: u32 = 99999;
+pub const RATE_PRECISION: i128 = 1_000_000_000_000_000_000;
+pub const DAY: u64 = 24 * 60 * 60;
+
+// Stellar Asset Contract balances must fit in an i64
+const SAC_MINT_AMOUNT: i128 = 1_000_000_000_000_000;
 
 // Create Asset token contract
 mod asset_token {
@@ -14,10 +19,14 @@ mod asset_token {
 }
 use asset_token::AssetTokenClient;
 
-fn create_asset_token_contract<'a>(e: &Env, admin: &Address) -> AssetTokenClient<'a> {
-    let contract_address = e.register_contract_wasm(None, asset_token::WASM);
+fn create_asset_token_contract<'a>(
+    e: &Env,
+    admin: &Address,
+    decimal: u32,
+) -> AssetTokenClient<'a> {
+    let contract_address = e.register(asset_token::WASM, ());
     let asset_token = AssetTokenClient::new(e, &contract_address);
-    asset_token.initialize(admin, &8, &"WBTC".into_val(e), &"WBTC".into_val(e));
+    asset_token.initialize(admin, &decimal, &"WBTC".into_val(e), &"WBTC".into_val(e));
     asset_token
 }
 
@@ -33,7 +42,7 @@ fn create_pump_token_contract<'a>(
     admin: &Address,
     minter: &Address,
 ) -> PumpTokenClient<'a> {
-    let contract_address = e.register_contract_wasm(None, pump_token::WASM);
+    let contract_address = e.register(pump_token::WASM, ());
     let pump_token = PumpTokenClient::new(e, &contract_address);
 
     // Initialize the pump token contract
@@ -55,7 +64,7 @@ pub mod pumpbtc_staking {
 use pumpbtc_staking::PumpBTCStakingClient;
 
 fn create_pumpbtc_staking_contract<'a>(e: &Env) -> PumpBTCStakingClient<'a> {
-    let pumpbtc_staking_address = &e.register_contract_wasm(None, pumpbtc_staking::WASM);
+    let pumpbtc_staking_address = &e.register(pumpbtc_staking::WASM, ());
     let pumpbtc_staking = PumpBTCStakingClient::new(e, pumpbtc_staking_address);
     pumpbtc_staking
 }
@@ -74,27 +83,79 @@ pub struct PumpBTCStakingTest<'a> {
 
 impl<'a> PumpBTCStakingTest<'a> {
     pub fn setup() -> Self {
+        Self::setup_with_asset_decimal(8)
+    }
+
+    pub fn setup_with_asset_decimal(asset_decimal: u32) -> Self {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
-        let user1 = Address::generate(&env);
-        let user2 = Address::generate(&env);
-        let operator = Address::generate(&env);
-
         let contract = create_pumpbtc_staking_contract(&env);
 
         let pump_token_contract = create_pump_token_contract(&env, &admin, &contract.address);
-        let asset_token_contract = create_asset_token_contract(&env, &admin);
+        let asset_token_contract = create_asset_token_contract(&env, &admin, asset_decimal);
+
+        Self::funded(
+            env,
+            contract,
+            pump_token_contract,
+            asset_token_contract,
+            admin,
+            10_000_000_000_000_000_000,
+        )
+    }
+
+    // The asset is a Stellar Asset Contract, and so is pumpBTC when `sac_pump`
+    // is set, with the staking contract as its admin
+    pub fn setup_with_sac(sac_pump: bool) -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let contract = create_pumpbtc_staking_contract(&env);
+
+        let pump_token_contract = if sac_pump {
+            let sac = env.register_stellar_asset_contract_v2(contract.address.clone());
+            PumpTokenClient::new(&env, &sac.address())
+        } else {
+            create_pump_token_contract(&env, &admin, &contract.address)
+        };
+        // Revocable, so tests can deauthorize balances
+        let sac = env.register_stellar_asset_contract_v2(admin.clone());
+        sac.issuer().set_flag(IssuerFlags::RevocableFlag);
+        let asset_token_contract = AssetTokenClient::new(&env, &sac.address());
+
+        Self::funded(
+            env,
+            contract,
+            pump_token_contract,
+            asset_token_contract,
+            admin,
+            SAC_MINT_AMOUNT,
+        )
+    }
+
+    fn funded(
+        env: Env,
+        contract: PumpBTCStakingClient<'a>,
+        pump_token_contract: PumpTokenClient<'a>,
+        asset_token_contract: AssetTokenClient<'a>,
+        admin: Address,
+        mint_amount: i128,
+    ) -> Self {
+        let user1 = Address::generate(&env);
+        let user2 = Address::generate(&env);
+        let operator = Address::generate(&env);
 
-        asset_token_contract.mint(&user1, &10_000_000_000_000_000_000);
-        asset_token_contract.mint(&user2, &10_000_000_000_000_000_000);
-        asset_token_contract.mint(&admin, &10_000_000_000_000_000_000);
-        asset_token_contract.mint(&operator, &10_000_000_000_000_000_000);
+        asset_token_contract.mint(&user1, &mint_amount);
+        asset_token_contract.mint(&user2, &mint_amount);
+        asset_token_contract.mint(&admin, &mint_amount);
+        asset_token_contract.mint(&operator, &mint_amount);
 
-        pump_token_contract.mint(&user1, &10_000_000_000_000_000_000);
-        pump_token_contract.mint(&user2, &10_000_000_000_000_000_000);
-        pump_token_contract.mint(&admin, &10_000_000_000_000_000_000);
+        pump_token_contract.mint(&user1, &mint_amount);
+        pump_token_contract.mint(&user2, &mint_amount);
+        pump_token_contract.mint(&admin, &mint_amount);
 
         env.cost_estimate().budget().reset_unlimited();
 
@@ -111,13 +172,39 @@ impl<'a> PumpBTCStakingTest<'a> {
     }
 
     pub fn setup_initialized() -> Self {
-        let test = Self::setup();
+        Self::setup().initialized()
+    }
+
+    pub fn setup_initialized_with_asset_decimal(asset_decimal: u32) -> Self {
+        Self::setup_with_asset_decimal(asset_decimal).initialized()
+    }
+
+    pub fn setup_initialized_with_sac_asset() -> Self {
+        Self::setup_with_sac(false).initialized()
+    }
+
+    pub fn setup_initialized_with_sac_pump() -> Self {
+        let test = Self::setup_with_sac(true);
+        test.contract.initialize_sac_pump(
+            &test.admin,
+            &test.pump_token.address,
+            &test.asset_token.address,
+        );
+        test.configured()
+    }
+
+    fn initialized(self) -> Self {
+        let test = self;
         test.contract.initialize(
             &test.admin,
             &test.pump_token.address,
             &test.asset_token.address,
         );
+        test.configured()
+    }
 
+    fn configured(self) -> Self {
+        let test = self;
         test.contract.set_stake_asset_cap(&(10_000_000_000i128));
         test.contract.set_operator(&test.operator);
         test.contract.set_normal_unstake_fee(&100); // 1%
@@ -126,3 +213,16 @@ impl<'a> PumpBTCStakingTest<'a> {
         test
     }
 }
+
+pub fn advance(test: &PumpBTCStakingTest, seconds: u64) {
+    test.env.ledger().with_mut(|ledger| {
+        ledger.timestamp += seconds;
+    });
+}
+
+// A user with asset to stake and no pumpBTC yet
+pub fn new_staker(test: &PumpBTCStakingTest) -> Address {
+    let user = Address::generate(&test.env);
+    test.asset_token.mint(&user, &(STAKING_AMOUNT * 10));
+    user
+}
@@ -0,0 +1,250 @@
+use soroban_sdk::{token, Address, Env, IntoVal, InvokeError, Symbol};
+
+use crate::error::PumpBTCStakingError;
+use crate::math::pump_token_amount_exact;
+use crate::storage::{
+    read_asset_token_address, read_pump_token_address, read_pump_token_kind, PumpTokenKind,
+};
+
+// Token calls go through the `try_` variants so that a failing transfer, mint
+// or burn surfaces as a typed error instead of an opaque host panic. Balances
+// and allowances are checked first to tell the common failures apart.
+
+pub fn transfer_token(
+    e: &Env,
+    token: &Address,
+    from: &Address,
+    to: &Address,
+    amount: i128,
+) -> Result<(), PumpBTCStakingError> {
+    let client = token::Client::new(e, token);
+    if client.balance(from) < amount {
+        return Err(PumpBTCStakingError::InsufficientAssetBalance);
+    }
+    match client.try_transfer(from, to, &amount) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(PumpBTCStakingError::AssetTransferFailed),
+    }
+}
+
+pub fn transfer_asset(
+    e: &Env,
+    from: &Address,
+    to: &Address,
+    amount: i128,
+) -> Result<(), PumpBTCStakingError> {
+    transfer_token(e, &read_asset_token_address(e), from, to, amount)
+}
+
+// Pulls the asset from `from` through an allowance granted to this contract
+pub fn transfer_asset_from(
+    e: &Env,
+    from: &Address,
+    to: &Address,
+    amount: i128,
+) -> Result<(), PumpBTCStakingError> {
+    let client = token::Client::new(e, &read_asset_token_address(e));
+    let spender = e.current_contract_address();
+    if client.allowance(from, &spender) < amount {
+        return Err(PumpBTCStakingError::InsufficientAllowance);
+    }
+    if client.balance(from) < amount {
+        return Err(PumpBTCStakingError::InsufficientAssetBalance);
+    }
+    match client.try_transfer_from(&spender, from, to, &amount) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(PumpBTCStakingError::AssetTransferFailed),
+    }
+}
+
+// Mints pumpBTC given in 8-decimal units, converted to the pump token's own
+// decimals
+pub fn mint_pump(e: &Env, to: &Address, amount: i128) -> Result<(), PumpBTCStakingError> {
+    let pump_token = read_pump_token_address(e);
+    let pump_amount = pump_token_amount_exact(e, amount)?;
+    let minted = match read_pump_token_kind(e) {
+        PumpTokenKind::Minter => matches!(
+            e.try_invoke_contract::<(), InvokeError>(
+                &pump_token,
+                &Symbol::new(e, "mint"),
+                (to.clone(), pump_amount).into_val(e),
+            ),
+            Ok(Ok(()))
+        ),
+        PumpTokenKind::StellarAsset => matches!(
+            token::StellarAssetClient::new(e, &pump_token).try_mint(to, &pump_amount),
+            Ok(Ok(()))
+        ),
+    };
+    if !minted {
+        return Err(PumpBTCStakingError::PumpTokenCallFailed);
+    }
+    Ok(())
+}
+
+// Spreads `amount` of rewards over the pumpBTC supply through the pump
+// token's reward accumulator. This fails when there is no supply to reward.
+// A Stellar Asset Contract has no such hook.
+pub fn notify_pump_reward(e: &Env, amount: i128) -> Result<(), PumpBTCStakingError> {
+    if read_pump_token_kind(e) != PumpTokenKind::Minter {
+        return Err(PumpBTCStakingError::UnsupportedPumpToken);
+    }
+    match e.try_invoke_contract::<i128, InvokeError>(
+        &read_pump_token_address(e),
+        &Symbol::new(e, "notify_reward"),
+        (amount,).into_val(e),
+    ) {
+        Ok(Ok(_)) => Ok(()),
+        _ => Err(PumpBTCStakingError::PumpTokenCallFailed),
+    }
+}
+
+// Takes the rewards accrued by `user` out of the pump token's ledger, to be
+// paid out by the caller
+pub fn claim_pump_rewards(e: &Env, user: &Address) -> Result<i128, PumpBTCStakingError> {
+    if read_pump_token_kind(e) != PumpTokenKind::Minter {
+        return Err(PumpBTCStakingError::UnsupportedPumpToken);
+    }
+    match e.try_invoke_contract::<i128, InvokeError>(
+        &read_pump_token_address(e),
+        &Symbol::new(e, "claim_rewards"),
+        (user.clone(),).into_val(e),
+    ) {
+        Ok(Ok(amount)) => Ok(amount),
+        _ => Err(PumpBTCStakingError::PumpTokenCallFailed),
+    }
+}
+
+pub fn pump_accrued_rewards(e: &Env, user: &Address) -> i128 {
+    if read_pump_token_kind(e) != PumpTokenKind::Minter {
+        return 0;
+    }
+    e.invoke_contract(
+        &read_pump_token_address(e),
+        &Symbol::new(e, "accrued_rewards"),
+        (user.clone(),).into_val(e),
+    )
+}
+
+// Locks pumpBTC in the holder's balance, with `boost` extra weight for
+// rewards and points. Only the custom pump token supports locks.
+pub fn lock_pump(
+    e: &Env,
+    user: &Address,
+    amount: i128,
+    boost: i128,
+) -> Result<(), PumpBTCStakingError> {
+    call_pump_lock(e, "lock", user, amount, boost)
+}
+
+pub fn unlock_pump(
+    e: &Env,
+    user: &Address,
+    amount: i128,
+    boost: i128,
+) -> Result<(), PumpBTCStakingError> {
+    call_pump_lock(e, "unlock", user, amount, boost)
+}
+
+fn call_pump_lock(
+    e: &Env,
+    function: &str,
+    user: &Address,
+    amount: i128,
+    boost: i128,
+) -> Result<(), PumpBTCStakingError> {
+    if read_pump_token_kind(e) != PumpTokenKind::Minter {
+        return Err(PumpBTCStakingError::UnsupportedPumpToken);
+    }
+    match e.try_invoke_contract::<(), InvokeError>(
+        &read_pump_token_address(e),
+        &Symbol::new(e, function),
+        (user.clone(), amount, boost).into_val(e),
+    ) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(PumpBTCStakingError::PumpTokenCallFailed),
+    }
+}
+
+// Burns pumpBTC given in 8-decimal units. The custom pump token lets the
+// minter burn directly; a Stellar Asset Contract burn needs the holder's
+// authorization, or an allowance when `from_allowance` is set.
+pub fn burn_pump(
+    e: &Env,
+    from: &Address,
+    amount: i128,
+    from_allowance: bool,
+) -> Result<(), PumpBTCStakingError> {
+    let pump_token = read_pump_token_address(e);
+    let pump_amount = pump_token_amount_exact(e, amount)?;
+    let client = token::Client::new(e, &pump_token);
+    if client.balance(from) < pump_amount {
+        return Err(PumpBTCStakingError::InsufficientPumpBalance);
+    }
+
+    let burned = match read_pump_token_kind(e) {
+        PumpTokenKind::Minter => {
+            // Locked pumpBTC cannot be burned until its lock ends. A pump
+            // token without locks has nothing locked.
+            let locked = match e.try_invoke_contract::<i128, InvokeError>(
+                &pump_token,
+                &Symbol::new(e, "locked_balance"),
+                (from.clone(),).into_val(e),
+            ) {
+                Ok(Ok(locked)) => locked,
+                _ => 0,
+            };
+            if client.balance(from) - locked < pump_amount {
+                return Err(PumpBTCStakingError::InsufficientPumpBalance);
+            }
+            matches!(
+                e.try_invoke_contract::<(), InvokeError>(
+                    &pump_token,
+                    &Symbol::new(e, "burn"),
+                    (from.clone(), pump_amount).into_val(e),
+                ),
+                Ok(Ok(()))
+            )
+        }
+        PumpTokenKind::StellarAsset if from_allowance => {
+            let spender = e.current_contract_address();
+            if client.allowance(from, &spender) < pump_amount {
+                return Err(PumpBTCStakingError::InsufficientAllowance);
+            }
+            matches!(
+                client.try_burn_from(&spender, from, &pump_amount),
+                Ok(Ok(()))
+            )
+        }
+        PumpTokenKind::StellarAsset => {
+            matches!(client.try_burn(from, &pump_amount), Ok(Ok(())))
+        }
+    };
+    if !burned {
+        return Err(PumpBTCStakingError::PumpTokenCallFailed);
+    }
+    Ok(())
+}
+
+// Moves pumpBTC given in 8-decimal units from `from` to `to` through an
+// allowance granted to this contract
+pub fn transfer_pump_from(
+    e: &Env,
+    from: &Address,
+    to: &Address,
+    amount: i128,
+) -> Result<(), PumpBTCStakingError> {
+    let pump_amount = pump_token_amount_exact(e, amount)?;
+    let client = token::Client::new(e, &read_pump_token_address(e));
+    let spender = e.current_contract_address();
+    if client.allowance(from, &spender) < pump_amount {
+        return Err(PumpBTCStakingError::InsufficientAllowance);
+    }
+    if client.balance(from) < pump_amount {
+        return Err(PumpBTCStakingError::InsufficientPumpBalance);
+    }
+    match client.try_transfer_from(&spender, from, to, &pump_amount) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(PumpBTCStakingError::PumpTokenCallFailed),
+    }
+}
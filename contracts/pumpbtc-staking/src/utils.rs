@@ -1,11 +1,25 @@
-use soroban_sdk::Env;
+use soroban_sdk::{token, Address, Env, Vec};
 
 use crate::error::PumpBTCStakingError;
+use crate::event;
+use crate::math::{safe_add, safe_sub, to_pump_amount, Rounding};
 use crate::storage::{
-    read_only_allow_stake, read_paused, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
-    MAX_DATE_SLOT, SECONDS_PER_DAY, UTC_OFFSET,
+    read_asset_token_address, read_collected_fee, read_only_allow_stake, read_operator_paused,
+    read_paused, read_pending_stake_amount, read_reward_reserve, read_schema_version,
+    read_total_claimable_amount, read_total_requested_amount, read_total_staking_amount,
+    read_withdraw_limit, read_withdraw_outflows, read_withdraw_window, write_invariant_violation,
+    write_operator_paused, write_paused, write_withdraw_outflows, Invariant, WithdrawOutflow,
+    INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD, MAX_DATE_SLOT, SCHEMA_VERSION,
+    SECONDS_PER_DAY, UTC_OFFSET, WITHDRAW_WINDOW_BUCKETS,
 };
 
+pub fn check_schema_version(e: &Env) -> Result<(), PumpBTCStakingError> {
+    if read_schema_version(e) != SCHEMA_VERSION {
+        return Err(PumpBTCStakingError::SchemaVersionMismatch);
+    }
+    Ok(())
+}
+
 pub fn check_not_paused(e: &Env) -> Result<(), PumpBTCStakingError> {
     if read_paused(e) {
         return Err(PumpBTCStakingError::ContractIsPaused);
@@ -13,6 +27,144 @@ pub fn check_not_paused(e: &Env) -> Result<(), PumpBTCStakingError> {
     Ok(())
 }
 
+pub fn check_operator_not_paused(e: &Env) -> Result<(), PumpBTCStakingError> {
+    if read_operator_paused(e) {
+        return Err(PumpBTCStakingError::OperatorFlowsPaused);
+    }
+    Ok(())
+}
+
+// Operator outflows still inside the rolling withdrawal window. Outflows are
+// grouped in buckets of 1/WITHDRAW_WINDOW_BUCKETS of the window, and a bucket
+// only leaves the window a full window after it closes. An outflow so weighs
+// on the limit for at least one window, and no span of one window can move
+// more than the limit.
+fn window_outflows(e: &Env) -> Vec<WithdrawOutflow> {
+    let block_timestamp = e.ledger().timestamp();
+    let withdraw_window = read_withdraw_window(e);
+    let mut outflows = Vec::new(e);
+    for outflow in read_withdraw_outflows(e).iter() {
+        if outflow.bucket_end.saturating_add(withdraw_window) > block_timestamp {
+            outflows.push_back(outflow);
+        }
+    }
+    outflows
+}
+
+fn total_outflow(outflows: &Vec<WithdrawOutflow>) -> Result<i128, PumpBTCStakingError> {
+    let mut total = 0;
+    for outflow in outflows.iter() {
+        total = safe_add(total, outflow.amount)?;
+    }
+    Ok(total)
+}
+
+// Remaining operator outflow allowed in the rolling window, `i128::MAX` when
+// no limit is set
+pub fn remaining_withdraw_allowance(e: &Env) -> Result<i128, PumpBTCStakingError> {
+    let withdraw_limit = read_withdraw_limit(e);
+    if withdraw_limit == 0 {
+        return Ok(i128::MAX);
+    }
+    let outflow = total_outflow(&window_outflows(e))?;
+    Ok(safe_sub(withdraw_limit, outflow)?.max(0))
+}
+
+// Books an operator outflow against the rolling window. When the limit would
+// be exceeded, operator flows are paused and `false` is returned: the caller
+// must then skip the transfer but still succeed, so the pause sticks.
+pub fn consume_withdraw_allowance(
+    e: &Env,
+    operator: &Address,
+    amount: i128,
+) -> Result<bool, PumpBTCStakingError> {
+    let withdraw_limit = read_withdraw_limit(e);
+    if withdraw_limit == 0 || amount <= 0 {
+        return Ok(true);
+    }
+
+    let mut outflows = window_outflows(e);
+    let outflow = total_outflow(&outflows)?;
+    if safe_add(outflow, amount)? > withdraw_limit {
+        write_operator_paused(e, true);
+        event::withdraw_limit_exceeded(
+            e,
+            operator.clone(),
+            amount,
+            safe_sub(withdraw_limit, outflow)?.max(0),
+        );
+        return Ok(false);
+    }
+
+    let block_timestamp = e.ledger().timestamp();
+    let bucket_length = read_withdraw_window(e).div_ceil(WITHDRAW_WINDOW_BUCKETS);
+    let bucket_end = block_timestamp - block_timestamp % bucket_length + bucket_length;
+    match outflows.last() {
+        Some(last) if last.bucket_end == bucket_end => {
+            let index = outflows.len() - 1;
+            outflows.set(
+                index,
+                WithdrawOutflow {
+                    bucket_end,
+                    amount: safe_add(last.amount, amount)?,
+                },
+            );
+        }
+        _ => outflows.push_back(WithdrawOutflow { bucket_end, amount }),
+    }
+    write_withdraw_outflows(e, &outflows);
+    Ok(true)
+}
+
+// Asserts the accounting invariants at the end of a state-changing call. A
+// breach pauses the contract until the admin investigates and unpauses; the
+// call itself still succeeds so that the pause is persisted. Breaches that can
+// be seen before moving funds, such as a claim above the deposited amount,
+// fail the call instead.
+pub fn enforce_invariants(e: &Env) -> Result<(), PumpBTCStakingError> {
+    let total_staking_amount = read_total_staking_amount(e);
+    let total_requested_amount = read_total_requested_amount(e);
+    let total_claimable_amount = read_total_claimable_amount(e);
+    let pending_stake_amount = read_pending_stake_amount(e);
+    let collected_fee = read_collected_fee(e);
+
+    let asset_token = read_asset_token_address(e);
+    let balance = token::Client::new(e, &asset_token).balance(&e.current_contract_address());
+
+    let invariant = if total_requested_amount < 0 {
+        Some(Invariant::NegativeRequested)
+    } else if total_staking_amount < 0
+        || total_claimable_amount < 0
+        || pending_stake_amount < 0
+        || collected_fee < 0
+        || read_reward_reserve(e) < 0
+    {
+        Some(Invariant::NegativeTotal)
+    } else if total_claimable_amount > to_pump_amount(e, balance, Rounding::Down)? {
+        Some(Invariant::ClaimableExceedsBalance)
+    } else {
+        None
+    };
+
+    if let Some(invariant) = invariant {
+        if !read_paused(e) {
+            write_paused(e, true);
+            write_invariant_violation(e, invariant);
+            event::invariant_violated(
+                e,
+                invariant,
+                balance,
+                total_staking_amount,
+                total_requested_amount,
+                total_claimable_amount,
+                pending_stake_amount,
+                collected_fee,
+            );
+        }
+    }
+    Ok(())
+}
+
 pub fn check_unstake_allowed(e: &Env) -> Result<(), PumpBTCStakingError> {
     if read_only_allow_stake(e) {
         return Err(PumpBTCStakingError::OnlyAllowStakeAtFirst);
@@ -21,7 +173,20 @@ pub fn check_unstake_allowed(e: &Env) -> Result<(), PumpBTCStakingError> {
 }
 
 pub fn get_date_slot(timestamp: u64) -> u32 {
-    (((timestamp + UTC_OFFSET) / SECONDS_PER_DAY) % (MAX_DATE_SLOT as u64)) as u32
+    (get_day(timestamp) % (MAX_DATE_SLOT as u64)) as u32
+}
+
+pub fn get_day(timestamp: u64) -> u64 {
+    (timestamp + UTC_OFFSET) / SECONDS_PER_DAY
+}
+
+pub fn get_day_start_time(day: u64) -> u64 {
+    (day * SECONDS_PER_DAY).saturating_sub(UTC_OFFSET)
+}
+
+// Day on which an unstake requested at `timestamp` becomes claimable
+pub fn get_maturity_day(timestamp: u64) -> u64 {
+    get_day(timestamp) + (MAX_DATE_SLOT as u64 - 1)
 }
 
 pub fn extend_instance_ttl(e: &Env) {